@@ -0,0 +1,20 @@
+fn main() {
+    // 启用capi特性时在OUT_DIR生成C头文件, 设置RUST_DRG_WRITE_HEADER=1时同时更新发布的include/rust_drg.h
+    #[cfg(feature = "capi")]
+    {
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-env-changed=RUST_DRG_WRITE_HEADER");
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        let out_dir = std::env::var("OUT_DIR").unwrap();
+        let bindings = cbindgen::Builder::new()
+            .with_crate(&crate_dir)
+            .with_config(cbindgen::Config::from_file("cbindgen.toml").unwrap())
+            .generate()
+            .expect("unable to generate C header");
+        bindings.write_to_file(std::path::Path::new(&out_dir).join("rust_drg.h"));
+        if std::env::var("RUST_DRG_WRITE_HEADER").is_ok_and(|v| v == "1") {
+            bindings.write_to_file("include/rust_drg.h");
+        }
+    }
+}
@@ -0,0 +1,143 @@
+#ifndef RUST_DRG_H
+#define RUST_DRG_H
+
+/* 本文件由cbindgen生成, 请勿手动修改 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define DRG_ABI_VERSION 1
+
+typedef struct DrgGrouper DrgGrouper;
+
+/*
+ C结构体形式返回的分组结果, 需要使用drg_result_free释放
+
+ 以后增加的字段只追加在末尾
+ */
+typedef struct DrgResultC {
+  char *id;
+  char *mdc;
+  char *adrg;
+  char *drg;
+  /*
+   分组说明(创伤部位、QY手术与CC/MCC), 每行一项, 以'\n'分隔
+   */
+  char *explain;
+  /*
+   编码审核问题, 格式同批量导出的audit列(级别:规则:编码, 以';'分隔), 没有问题时为空字符串
+   */
+  char *audit;
+} DrgResultC;
+
+/*
+ C结构体形式传入的病例, 字符串均为UTF-8且以'\0'结尾, 主手术可为NULL
+
+ weight及之前的字段必须填写; 之后的字段(以及以后追加在末尾的字段)由struct_size判断调用方是否包含,
+ 不包含时视为未填写, 因此按旧头文件编译的调用方不需要重新编译
+ */
+typedef struct DrgCaseC {
+  /*
+   调用方填写sizeof(DrgCaseC)
+   */
+  uintptr_t struct_size;
+  const char *id;
+  const char *main_dis;
+  const char *main_opt;
+  const char *const *other_dis;
+  uintptr_t other_dis_len;
+  const char *const *other_opt;
+  uintptr_t other_opt_len;
+  /*
+   GB/T 2261.1编码(0 => 未知, 1 => 男, 2 => 女, 9 => 未说明)
+   */
+  int64_t sex;
+  double age;
+  int64_t weight;
+  /*
+   年龄不足1周岁天数, 小于0表示未填写
+   */
+  int64_t age_days;
+  /*
+   新生儿出生体重(克), 小于0表示未填写
+   */
+  int64_t birth_weight;
+  /*
+   新生儿入院体重(克), 小于0表示未填写
+   */
+  int64_t admission_weight;
+  /*
+   有创呼吸机使用时间(小时), 小于0或NaN表示未填写
+   */
+  double vent_hours;
+} DrgCaseC;
+
+/*
+ C接口版本, 调用方可以据此确认动态库与头文件是否匹配
+ */
+uint32_t drg_abi_version(void);
+
+/*
+ 从分组方案目录创建分组器, 失败返回NULL, 错误信息见`drg_last_error`
+
+ # Safety
+ `scheme_dir`必须是以'\0'结尾的UTF-8字符串
+ */
+struct DrgGrouper *drg_grouper_new(const char *scheme_dir);
+
+/*
+ 释放分组器
+
+ # Safety
+ `grouper`必须是`drg_grouper_new`返回的指针或NULL, 且只能释放一次
+ */
+void drg_grouper_free(struct DrgGrouper *grouper);
+
+/*
+ 对C结构体形式的病例进行分组, 失败返回NULL
+
+ `struct_size`填写为`sizeof(DrgCaseC)`, 不小于`offsetof(DrgCaseC, age_days)`
+
+ # Safety
+ `grouper`必须是有效的分组器, `record`中的字符串与数组指针在调用期间必须有效
+ */
+struct DrgResultC *drg_grouper_group(const struct DrgGrouper *grouper,
+                                     const struct DrgCaseC *record);
+
+/*
+ 释放`drg_grouper_group`返回的分组结果
+
+ # Safety
+ `result`必须是`drg_grouper_group`返回的指针或NULL, 且只能释放一次
+ */
+void drg_result_free(struct DrgResultC *result);
+
+/*
+ 对JSON形式的病例进行分组, 返回JSON形式的分组结果, 失败返回NULL
+
+ 病例字段为id、main_dis、main_opt、other_dis、other_opt、sex、age、weight,
+ 结果字段同管道模式的输出, 需要使用`drg_string_free`释放
+
+ # Safety
+ `grouper`必须是有效的分组器, `case_json`必须是以'\0'结尾的UTF-8字符串
+ */
+char *drg_grouper_group_json(const struct DrgGrouper *grouper, const char *case_json);
+
+/*
+ 释放本库返回的字符串
+
+ # Safety
+ `s`必须是本库返回的字符串或NULL, 且只能释放一次
+ */
+void drg_string_free(char *s);
+
+/*
+ 当前线程最近一次调用的错误信息, 没有错误时返回NULL
+
+ 返回的字符串由本库持有, 在本线程下一次调用前有效, 不需要释放
+ */
+const char *drg_last_error(void);
+
+#endif /* RUST_DRG_H */
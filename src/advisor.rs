@@ -0,0 +1,112 @@
+use crate::audit::principal_findings;
+use crate::case::DrgCase;
+use crate::group::assign_drg;
+use crate::scheme::DrgScheme;
+use serde::Serialize;
+use std::string::String;
+
+// 一种主诊断与主手术的选择及其分组结果
+#[derive(Debug, Clone, Serialize)]
+pub struct PrincipalChoice {
+    pub main_dis: String,    // 主诊断编码
+    pub main_opt: String,    // 主手术编码
+    pub drg: String,         // 分组结果
+    pub weight: Option<f64>, // DRG权重, 只用于展示, 权重表中没有时为None
+    pub valid: bool,         // 是否正常入组(非QY、非KBBZ)
+    pub issues: Vec<String>, // 主诊断违反的不能作为主诊断的审核规则
+    pub current: bool,       // 是否为病例当前的选择
+}
+
+// 主诊断/主手术选择建议
+#[derive(Debug, Clone, Serialize)]
+pub struct PrincipalAdvice {
+    pub id: String,                    // 病例ID
+    pub choices: Vec<PrincipalChoice>, // 所有选择, 按推荐顺序排列
+    pub differs: bool,                 // 推荐的选择与当前选择不同
+}
+
+impl PrincipalAdvice {
+    // 当前的选择
+    pub fn current(&self) -> &PrincipalChoice {
+        self.choices.iter().find(|c| c.current).unwrap()
+    }
+
+    // 推荐的选择
+    pub fn preferred(&self) -> &PrincipalChoice {
+        &self.choices[0]
+    }
+}
+
+// 对一种选择进行分组
+fn group_choice(record: &DrgCase, scheme: &DrgScheme, current: bool) -> PrincipalChoice {
+    let result = assign_drg(record, scheme);
+    PrincipalChoice {
+        main_dis: record.main_dis.clone(),
+        main_opt: record.main_opt.clone(),
+        weight: scheme.drg_weight.get(&result.drg).copied(),
+        valid: result.adrg != "KBBZ" && result.qy_operations.is_empty(),
+        issues: principal_findings(record, scheme)
+            .into_iter()
+            .map(|f| f.rule)
+            .collect(),
+        drg: result.drg,
+        current,
+    }
+}
+
+// 依次以各个其他诊断为主诊断、各个其他手术为主手术进行分组, 按编码规则推荐主诊断与主手术:
+// 主诊断不能违反不能作为主诊断的审核规则, 主诊断应与主手术对应(不进入QY或无法入组),
+// 满足规则时保留当前选择, 否则优先改动最少的选择; 权重只用于展示, 不参与推荐
+pub fn advise_principal(record: &DrgCase, scheme: &DrgScheme) -> PrincipalAdvice {
+    let mut dis_choices: Vec<Option<usize>> = vec![None];
+    dis_choices.extend((0..record.other_dis.len()).map(Some));
+    let mut opt_choices: Vec<Option<usize>> = vec![None];
+    opt_choices.extend((0..record.other_opt.len()).map(Some));
+
+    // 每种选择及其改动的主编码个数
+    let mut choices: Vec<(usize, PrincipalChoice)> = Vec::new();
+    for dis_idx in &dis_choices {
+        for opt_idx in &opt_choices {
+            let changes = dis_idx.is_some() as usize + opt_idx.is_some() as usize;
+            let variant = record.with_principal(*dis_idx, *opt_idx);
+            choices.push((changes, group_choice(&variant, scheme, changes == 0)));
+        }
+    }
+    // 稳定排序, 相同时保持其他诊断、其他手术的原有顺序
+    choices.sort_by_key(|(changes, c)| (!c.issues.is_empty(), !c.valid, !c.current, *changes));
+    let choices: Vec<PrincipalChoice> = choices.into_iter().map(|(_, c)| c).collect();
+    let differs = !choices[0].current;
+    PrincipalAdvice {
+        id: record.id.clone(),
+        choices,
+        differs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{sample_case, scheme};
+
+    #[test]
+    fn keeps_valid_current_choice_regardless_of_weight() {
+        let mut scheme = scheme();
+        let record = sample_case("J20.900", &["I21.000"], &[], 40.0);
+        // 其他诊断作为主诊断时权重更高, 但当前选择符合编码规则
+        let other = assign_drg(&record.with_principal(Some(0), None), &scheme).drg;
+        scheme.drg_weight.insert(other, 10.0);
+        let advice = advise_principal(&record, &scheme);
+        assert!(!advice.differs);
+        assert_eq!(advice.preferred().main_dis, "J20.900");
+    }
+
+    #[test]
+    fn flags_principal_that_breaks_coding_rules() {
+        let scheme = scheme();
+        // 外因编码不能作为主诊断
+        let advice = advise_principal(&sample_case("W19.x00", &["S72.000"], &[], 40.0), &scheme);
+        assert!(advice.differs);
+        assert_eq!(advice.preferred().main_dis, "S72.000");
+        assert!(!advice.current().issues.is_empty());
+    }
+}
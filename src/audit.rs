@@ -0,0 +1,313 @@
+use crate::case::{DrgCase, Sex};
+use crate::scheme::{DrgScheme, ValueBand};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::string::String;
+
+// 审核规则(audit_rule.json)=========================================================================
+// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,    // 提示
+    Warning, // 警告
+    Error,   // 错误
+}
+
+// 规则检查的编码范围
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditTarget {
+    #[default]
+    Dis, // 主诊断与其他诊断
+    Opt, // 主手术与其他手术
+}
+
+// 编码匹配条件, 满足任一条件即匹配
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CodePattern {
+    pub codes: HashSet<String>, // 完整编码
+    pub prefix: Vec<String>,    // 编码前缀
+    pub asterisk: bool,         // 单独使用的星号编码(以*结尾且不含+的编码)
+}
+
+impl CodePattern {
+    // 编码是否匹配
+    pub fn matches(&self, code: &str) -> bool {
+        self.codes.contains(code)
+            || self.prefix.iter().any(|p| code.starts_with(p.as_str()))
+            || (self.asterisk && code.ends_with('*') && !code.contains('+'))
+    }
+}
+
+// 规则的检查内容
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditCheck {
+    // 只适用于某一性别的编码
+    Sex {
+        sex: Sex,
+        #[serde(default)]
+        target: AuditTarget,
+        codes: CodePattern,
+    },
+    // 只适用于某一年龄段的编码, 年龄天数区间对满一岁的病例不满足
+    Age {
+        age_days: Option<ValueBand>,
+        age_years: Option<ValueBand>,
+        #[serde(default)]
+        target: AuditTarget,
+        codes: CodePattern,
+    },
+    // 不能作为主诊断的编码
+    NotPrincipal {
+        codes: CodePattern,
+    },
+    // 主诊断与其他诊断、主手术与其他手术之间的重复编码
+    Duplicate,
+    // 病例中没有与手术相关的诊断(按各MDC的诊断表与相关手术判断)
+    UnjustifiedOpt,
+    // 填写的年龄与出生日期、入院日期不一致
+    AgeDates,
+}
+
+// 一条审核规则
+#[derive(Debug, Clone, Deserialize)]
+pub struct AuditRule {
+    pub id: String,         // 规则编号
+    pub severity: Severity, // 严重程度
+    pub message: String,    // 问题说明
+    #[serde(flatten)]
+    pub check: AuditCheck, // 检查内容
+}
+
+// 审核结果==========================================================================================
+// 一条审核问题
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditFinding {
+    pub rule: String,       // 规则编号
+    pub severity: Severity, // 严重程度
+    pub code: String,       // 有问题的编码
+    pub message: String,    // 问题说明
+}
+
+impl AuditFinding {
+    fn new(rule: &AuditRule, code: &str) -> Self {
+        Self {
+            rule: rule.id.clone(),
+            severity: rule.severity,
+            code: code.to_string(),
+            message: rule.message.clone(),
+        }
+    }
+}
+
+// 病例中规则检查范围内的编码(按主编码在前的顺序)
+fn target_codes(record: &DrgCase, target: AuditTarget) -> Vec<&String> {
+    let (main, other) = match target {
+        AuditTarget::Dis => (&record.main_dis, &record.other_dis),
+        AuditTarget::Opt => (&record.main_opt, &record.other_opt),
+    };
+    std::iter::once(main)
+        .chain(other)
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+// 主编码与其他编码之间、其他编码之间重复的编码
+fn duplicate_codes<'a>(main: &'a String, other: &'a [String]) -> Vec<&'a String> {
+    let mut seen: HashSet<&String> = HashSet::new();
+    let mut duplicates: Vec<&String> = Vec::new();
+    for code in std::iter::once(main).chain(other) {
+        if !code.is_empty() && !seen.insert(code) && !duplicates.contains(&code) {
+            duplicates.push(code);
+        }
+    }
+    duplicates
+}
+
+// 手术是否属于先期分组(MDCA)的ADRG或呼吸机规则, 这些手术不依赖诊断
+fn is_pre_group_opt(scheme: &DrgScheme, opt: &str) -> bool {
+    let in_mdca = scheme.mdc_to_adrg.get("MDCA").is_some_and(|adrg_list| {
+        adrg_list.iter().any(|adrg| {
+            let prefix = format!("{}_", adrg);
+            scheme
+                .adrg_dis_opt
+                .iter()
+                .any(|(k, s)| k.starts_with(&prefix) && !k.ends_with("_dis") && s.contains(opt))
+        })
+    });
+    in_mdca || scheme.mdca_rule.values().any(|r| r.vent_opt.contains(opt))
+}
+
+// 手术是否有相关的诊断: 病例中的某个诊断所属MDC的相关手术包含该手术,
+// 先期分组的手术与不属于任何MDC相关手术的手术无法判断, 视为有相关诊断
+fn is_justified_opt(record: &DrgCase, scheme: &DrgScheme, opt: &str) -> bool {
+    let relevant_opt = &scheme.qy_rule.relevant_opt;
+    if is_pre_group_opt(scheme, opt) || !relevant_opt.values().any(|s| s.contains(opt)) {
+        return true;
+    }
+    relevant_opt.iter().any(|(mdc, opt_set)| {
+        opt_set.contains(opt)
+            && record.all_dis.iter().any(|d| {
+                if mdc == "MDCZ" {
+                    scheme.mdcz_dis.values().any(|s| s.contains(d))
+                } else {
+                    scheme.mdc_dis.get(mdc).is_some_and(|s| s.contains(d))
+                }
+            })
+    })
+}
+
+// 按一条规则审核病例
+fn apply_rule(record: &DrgCase, scheme: &DrgScheme, rule: &AuditRule) -> Vec<AuditFinding> {
+    let mut findings: Vec<AuditFinding> = Vec::new();
+    match &rule.check {
+        AuditCheck::Sex { sex, target, codes } => {
+            // 性别未知时无法判断
+            if record.sex != Sex::Unknown && record.sex != *sex {
+                for code in target_codes(record, *target) {
+                    if codes.matches(code) {
+                        findings.push(AuditFinding::new(rule, code));
+                    }
+                }
+            }
+        }
+        AuditCheck::Age {
+            age_days,
+            age_years,
+            target,
+            codes,
+        } => {
+            let days_ok = match age_days {
+                Some(band) => record
+                    .age_in_days()
+                    .is_some_and(|days| band.contains(days as f64)),
+                None => true,
+            };
+            let years_ok = match age_years {
+                Some(band) => band.contains(record.age),
+                None => true,
+            };
+            if !(days_ok && years_ok) {
+                for code in target_codes(record, *target) {
+                    if codes.matches(code) {
+                        findings.push(AuditFinding::new(rule, code));
+                    }
+                }
+            }
+        }
+        AuditCheck::NotPrincipal { codes } => {
+            if codes.matches(&record.main_dis) {
+                findings.push(AuditFinding::new(rule, &record.main_dis));
+            }
+        }
+        AuditCheck::Duplicate => {
+            for code in duplicate_codes(&record.main_dis, &record.other_dis)
+                .into_iter()
+                .chain(duplicate_codes(&record.main_opt, &record.other_opt))
+            {
+                findings.push(AuditFinding::new(rule, code));
+            }
+        }
+        AuditCheck::AgeDates => {
+            if let Some(message) = record.age_mismatch() {
+                findings.push(AuditFinding {
+                    rule: rule.id.clone(),
+                    severity: rule.severity,
+                    code: "age".to_string(),
+                    message: format!("{}: {}", rule.message, message),
+                });
+            }
+        }
+        AuditCheck::UnjustifiedOpt => {
+            for code in target_codes(record, AuditTarget::Opt) {
+                if scheme.all_opt_list.contains(code.as_str())
+                    && !is_justified_opt(record, scheme, code)
+                {
+                    findings.push(AuditFinding::new(rule, code));
+                }
+            }
+        }
+    }
+    findings
+}
+
+// 按分组方案中的审核规则检查病例编码, 按严重程度从高到低排列
+pub fn audit_case(record: &DrgCase, scheme: &DrgScheme) -> Vec<AuditFinding> {
+    let mut findings: Vec<AuditFinding> = scheme
+        .audit_rule
+        .iter()
+        .flat_map(|rule| apply_rule(record, scheme, rule))
+        .collect();
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+// 主诊断违反的不能作为主诊断的规则, 用于主诊断选择建议
+pub fn principal_findings(record: &DrgCase, scheme: &DrgScheme) -> Vec<AuditFinding> {
+    scheme
+        .audit_rule
+        .iter()
+        .filter(|rule| matches!(rule.check, AuditCheck::NotPrincipal { .. }))
+        .flat_map(|rule| apply_rule(record, scheme, rule))
+        .collect()
+}
+
+// 将审核问题合并为一个字符串, 用于表格导出
+pub fn format_findings(findings: &[AuditFinding]) -> String {
+    findings
+        .iter()
+        .map(|f| format!("{:?}:{}:{}", f.severity, f.rule, f.code).to_lowercase())
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::case::parse_date;
+    use crate::fixtures::{sample_case, scheme};
+
+    fn rules(findings: &[AuditFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.rule.as_str()).collect()
+    }
+
+    #[test]
+    fn pre_group_operations_are_justified() {
+        let scheme = scheme();
+        // ECMO、呼吸机与心脏移植只属于MDCA
+        for opts in [&["39.6500"][..], &["96.7201"], &["37.5100"]] {
+            let findings = audit_case(&sample_case("J96.000", &[], opts, 60.0), &scheme);
+            assert!(!rules(&findings).contains(&"unjustified_opt"), "{:?}", opts);
+        }
+    }
+
+    #[test]
+    fn age_mismatch_is_reported() {
+        let mut case = sample_case("J20.900", &[], &[], 29.0);
+        case.birth_date = parse_date("1990-01-01");
+        case.admission_date = parse_date("2020-06-01");
+        let findings = audit_case(&case, &scheme());
+        assert_eq!(rules(&findings), vec!["age_dates"]);
+        case.age = 30.0;
+        assert!(audit_case(&case, &scheme()).is_empty());
+    }
+
+    #[test]
+    fn newborn_only_codes_after_neonatal_period() {
+        let scheme = scheme();
+        // 活产婴儿(Z38)与受母体情况影响的新生儿(P00-P04)编码只用于新生儿期
+        let mut neonate = sample_case("Z38.000", &[], &[], 0.0);
+        neonate.age_days = Some(10);
+        assert!(audit_case(&neonate, &scheme).is_empty());
+        let findings = audit_case(&sample_case("Z38.000", &[], &[], 0.5), &scheme);
+        assert_eq!(rules(&findings), vec!["newborn_dis"]);
+        let findings = audit_case(&sample_case("J20.900", &["P00.000"], &[], 30.0), &scheme);
+        assert_eq!(rules(&findings), vec!["newborn_dis"]);
+        // 支气管肺发育不良等围生期疾病在新生儿期后仍可作为诊断
+        let findings = audit_case(&sample_case("P27.100", &[], &[], 0.5), &scheme);
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+}
@@ -0,0 +1,500 @@
+use crate::audit::{audit_case, format_findings};
+use crate::case::{CaseRow, DrgCase, RowError, OTHER_DIS_COLUMNS, OTHER_OPT_COLUMNS};
+use crate::group::{group_case, which_drg};
+use crate::incremental::RegroupStore;
+use crate::migration::{compare_schemes, hospital_impact, transition_matrix};
+use crate::scheme::DrgScheme;
+use crate::sensitivity::{analyze_case, summarize};
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+use std::string::String;
+
+// 读取表格文件✔
+pub fn from_csv_file(file_path: &str) -> Result<DataFrame, Box<dyn Error>> {
+    let res = CsvReader::from_path(file_path)?
+        .infer_schema(None)
+        .has_header(true)
+        .finish()?;
+    Ok(res)
+}
+
+// 表格中的一行, 各列已分别转换为文本与数值
+struct FrameRow<'a> {
+    text_cols: &'a HashMap<String, Series>,   // 转换为文本的列
+    number_cols: &'a HashMap<String, Series>, // 转换为数值的列(无法转换的值为空)
+    idx: usize,                               // 行号
+}
+
+impl CaseRow for FrameRow<'_> {
+    fn text(&self, key: &str) -> Result<Option<String>, RowError> {
+        let col = match self.text_cols.get(key) {
+            Some(col) => col,
+            None => return Ok(None),
+        };
+        let values = col.utf8().map_err(|e| RowError::Invalid(e.to_string()))?;
+        Ok(values
+            .get(self.idx)
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty()))
+    }
+
+    fn number(&self, key: &str) -> Result<Option<f64>, RowError> {
+        let col = match self.number_cols.get(key) {
+            Some(col) => col,
+            None => return Ok(None),
+        };
+        let values = col.f64().map_err(|e| RowError::Invalid(e.to_string()))?;
+        Ok(values.get(self.idx).filter(|v| !v.is_nan()))
+    }
+}
+
+// 从表格数据构造出DRG病例结构✔
+// 字段规则见DrgCase::from_row, 编码列统一按文本读取(AnyValue的Display会给文本带上双引号)
+pub fn construct_drg_case(df: &DataFrame) -> Result<Vec<DrgCase>, Box<dyn Error>> {
+    // 表格必须包含的列
+    let mut required = vec!["结算流水号", "主诊断编码", "主手术编码"];
+    required.extend(OTHER_DIS_COLUMNS);
+    required.extend(OTHER_OPT_COLUMNS);
+    required.extend(["性别", "体重"]);
+    for colname in required {
+        df.column(colname)?;
+    }
+    let mut text_cols: HashMap<String, Series> = HashMap::new();
+    let mut number_cols: HashMap<String, Series> = HashMap::new();
+    for col in df.get_columns() {
+        text_cols.insert(col.name().to_string(), col.cast(&DataType::Utf8)?);
+        if let Ok(number) = col.cast(&DataType::Float64) {
+            number_cols.insert(col.name().to_string(), number);
+        }
+    }
+    let mut my_vec: Vec<DrgCase> = Vec::new();
+    for idx in 0..df.height() {
+        let row = FrameRow {
+            text_cols: &text_cols,
+            number_cols: &number_cols,
+            idx,
+        };
+        let drg_case = DrgCase::from_row(&row).map_err(|e| format!("row {}: {}", idx + 1, e))?;
+        my_vec.push(drg_case);
+    }
+    return Ok(my_vec);
+}
+
+// 批量对表格数据进行DRG分组
+pub fn batch_drg_group(df: &DataFrame, scheme: &DrgScheme) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut pred_drg_list: Vec<String> = Vec::new();
+    let drg_case = construct_drg_case(df)?;
+    for case in drg_case {
+        let drg_pred = which_drg(&case, scheme);
+        pred_drg_list.push(drg_pred);
+    }
+    return Ok(pred_drg_list);
+}
+
+// 批量分组的汇总
+#[derive(Debug, Clone, Default)]
+pub struct BatchSummary {
+    pub cases: usize,        // 病例数
+    pub ungrouped: usize,    // 无法入组(KBBZ)的病例数
+    pub audit_issues: usize, // 有编码审核问题的病例数
+}
+
+// 对表格数据进行DRG分组并导出原表格及分组结果✔
+pub fn drg_group_and_export(
+    in_path: &str,
+    out_path: &str,
+    scheme: &DrgScheme, // 分组方案
+) -> Result<BatchSummary, Box<dyn Error>> {
+    drg_group_file(in_path, out_path, scheme, true)
+}
+
+// 同drg_group_and_export, progress为false时不输出进度(供监视目录模式使用, 避免与日志混在一起)
+pub fn drg_group_file(
+    in_path: &str,
+    out_path: &str,
+    scheme: &DrgScheme, // 分组方案
+    progress: bool,     // 是否输出进度
+) -> Result<BatchSummary, Box<dyn Error>> {
+    let report = |message: &str| {
+        if progress {
+            println!("{}", message);
+        }
+    };
+    // 读取CSV表格文件
+    report("reading data ... ");
+    let mut df = from_csv_file(in_path)?;
+    report("drg grouping ... ");
+    // 进行编码审核与DRG分组
+    let drg_case = construct_drg_case(&df)?;
+    let mut audit_list: Vec<String> = Vec::with_capacity(drg_case.len());
+    let mut drg_pred_list: Vec<String> = Vec::with_capacity(drg_case.len());
+    for case in &drg_case {
+        let result = group_case(case, scheme);
+        audit_list.push(format_findings(&result.audit));
+        drg_pred_list.push(result.drg);
+    }
+    let summary = BatchSummary {
+        cases: drg_case.len(),
+        ungrouped: drg_pred_list.iter().filter(|d| *d == "KBBZ").count(),
+        audit_issues: audit_list.iter().filter(|a| !a.is_empty()).count(),
+    };
+    // 创建Series序列准备添加到数据表中
+    let new_col = Series::new("clear_code", drg_pred_list);
+    let audit_col = Series::new("audit", audit_list);
+    // 定义需要添加的列的数据类型
+    let mut my_schema = Schema::new();
+    my_schema.with_column(String::from("clear_code"), DataType::UInt8);
+    my_schema.with_column(String::from("audit"), DataType::Utf8);
+    // 向表中添加列
+    df._add_columns(vec![new_col, audit_col], &my_schema)?;
+
+    // 将表格数据以CSV格式写入本地
+    let export_file = File::create(out_path)?;
+    report("data exporting ...");
+    CsvWriter::new(export_file)
+        .has_header(true)
+        .with_delimiter(b',')
+        .finish(&mut df)?;
+
+    Ok(summary)
+}
+
+// 对表格数据进行高编敏感性分析, 分别导出逐病例结果与按医疗机构、科室汇总的结果
+pub fn sensitivity_and_export(
+    in_path: &str,
+    out_path: &str,
+    summary_path: &str,
+    scheme: &DrgScheme, // 分组方案
+) -> Result<(), Box<dyn Error>> {
+    println!("reading data ... ");
+    let df = from_csv_file(in_path)?;
+    println!("analyzing ... ");
+    let reports: Vec<_> = construct_drg_case(&df)?
+        .iter()
+        .map(|case| analyze_case(case, scheme))
+        .collect();
+
+    // 逐病例结果, carriers为去掉后分组降低的编码, removals为去掉每个编码后的分组
+    let mut case_df = DataFrame::new(vec![
+        Series::new(
+            "结算流水号",
+            reports.iter().map(|r| r.id.clone()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "定点医疗机构代码",
+            reports
+                .iter()
+                .map(|r| r.hospital_code.clone().unwrap_or_default())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "出院科别",
+            reports
+                .iter()
+                .map(|r| r.department.clone().unwrap_or_default())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "drg",
+            reports.iter().map(|r| r.drg.clone()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "carriers",
+            reports
+                .iter()
+                .map(|r| {
+                    r.carriers()
+                        .iter()
+                        .map(|c| c.code.clone())
+                        .collect::<Vec<_>>()
+                        .join(";")
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "removals",
+            reports
+                .iter()
+                .map(|r| {
+                    r.removals
+                        .iter()
+                        .map(|c| format!("{}=>{}", c.code, c.drg))
+                        .collect::<Vec<_>>()
+                        .join(";")
+                })
+                .collect::<Vec<_>>(),
+        ),
+    ])?;
+
+    // 按医疗机构与科室汇总
+    let summary = summarize(&reports);
+    let mut summary_df = DataFrame::new(vec![
+        Series::new(
+            "定点医疗机构代码",
+            summary
+                .iter()
+                .map(|s| s.hospital_code.clone())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "出院科别",
+            summary
+                .iter()
+                .map(|s| s.department.clone())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "cases",
+            summary.iter().map(|s| s.cases as u64).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "sensitive_cases",
+            summary
+                .iter()
+                .map(|s| s.sensitive_cases as u64)
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "sensitive_rate",
+            summary
+                .iter()
+                .map(|s| s.sensitive_rate())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "dis_carriers",
+            summary
+                .iter()
+                .map(|s| s.dis_carriers as u64)
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "opt_carriers",
+            summary
+                .iter()
+                .map(|s| s.opt_carriers as u64)
+                .collect::<Vec<_>>(),
+        ),
+    ])?;
+
+    println!("data exporting ...");
+    for (path, frame) in [(out_path, &mut case_df), (summary_path, &mut summary_df)] {
+        CsvWriter::new(File::create(path)?)
+            .has_header(true)
+            .with_delimiter(b',')
+            .finish(frame)?;
+    }
+    Ok(())
+}
+
+// 用新旧两套分组方案对表格数据分组, 在导出目录中写出转移矩阵、各医疗机构的影响与新方案无法入组的病例
+pub fn migration_and_export(
+    in_path: &str,
+    out_dir: &str,
+    old_scheme: &DrgScheme, // 旧分组方案
+    new_scheme: &DrgScheme, // 新分组方案
+) -> Result<(), Box<dyn Error>> {
+    println!("reading data ... ");
+    let df = from_csv_file(in_path)?;
+    println!("drg grouping ... ");
+    let cases = compare_schemes(&construct_drg_case(&df)?, old_scheme, new_scheme);
+
+    // 旧DRG到新DRG的转移矩阵
+    let matrix = transition_matrix(&cases);
+    let mut matrix_df = DataFrame::new(vec![
+        Series::new(
+            "old_drg",
+            matrix.iter().map(|m| m.0.clone()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "new_drg",
+            matrix.iter().map(|m| m.1.clone()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "cases",
+            matrix.iter().map(|m| m.2 as u64).collect::<Vec<_>>(),
+        ),
+    ])?;
+
+    // 各医疗机构的病例数与权重变化
+    let impact = hospital_impact(&cases);
+    let mut impact_df = DataFrame::new(vec![
+        Series::new(
+            "定点医疗机构代码",
+            impact
+                .iter()
+                .map(|i| i.hospital_code.clone())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "cases",
+            impact.iter().map(|i| i.cases as u64).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "changed",
+            impact.iter().map(|i| i.changed as u64).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "ungroupable",
+            impact
+                .iter()
+                .map(|i| i.ungroupable as u64)
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "old_weight",
+            impact.iter().map(|i| i.old_weight).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "new_weight",
+            impact.iter().map(|i| i.new_weight).collect::<Vec<_>>(),
+        ),
+    ])?;
+
+    // 新方案无法入组的病例
+    let lost: Vec<_> = cases.iter().filter(|c| c.became_ungroupable()).collect();
+    let mut lost_df = DataFrame::new(vec![
+        Series::new(
+            "结算流水号",
+            lost.iter().map(|c| c.id.clone()).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "定点医疗机构代码",
+            lost.iter()
+                .map(|c| c.hospital_code.clone().unwrap_or_default())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "old_drg",
+            lost.iter().map(|c| c.old_drg.clone()).collect::<Vec<_>>(),
+        ),
+    ])?;
+
+    println!("data exporting ...");
+    let out_dir = Path::new(out_dir);
+    std::fs::create_dir_all(out_dir)?;
+    for (name, frame) in [
+        ("transition.csv", &mut matrix_df),
+        ("hospital.csv", &mut impact_df),
+        ("ungroupable.csv", &mut lost_df),
+    ] {
+        CsvWriter::new(File::create(out_dir.join(name))?)
+            .has_header(true)
+            .with_delimiter(b',')
+            .finish(frame)?;
+    }
+    Ok(())
+}
+
+// 增量分组: 只对新增或内容变化的病例重新分组, 导出全部分组结果与分组发生变化的病例
+pub fn incremental_and_export(
+    in_path: &str,
+    out_path: &str,
+    delta_path: &str,
+    store_path: &str,
+    scheme: &DrgScheme, // 分组方案
+) -> Result<(), Box<dyn Error>> {
+    println!("reading data ... ");
+    let mut df = from_csv_file(in_path)?;
+    let mut store = RegroupStore::load(store_path)?;
+    println!("drg grouping ... ");
+    let drg_case = construct_drg_case(&df)?;
+    let result = store.regroup(&drg_case, scheme);
+    println!(
+        "regrouped {} of {} cases, {} groupings changed",
+        result.regrouped,
+        df.height(),
+        result.delta.len()
+    );
+    // 全部分组结果, 列与多病例模式一致
+    let audit_list: Vec<String> = drg_case
+        .iter()
+        .map(|case| format_findings(&audit_case(case, scheme)))
+        .collect();
+    let new_col = Series::new("clear_code", result.drg);
+    let audit_col = Series::new("audit", audit_list);
+    let mut my_schema = Schema::new();
+    my_schema.with_column(String::from("clear_code"), DataType::Utf8);
+    my_schema.with_column(String::from("audit"), DataType::Utf8);
+    df._add_columns(vec![new_col, audit_col], &my_schema)?;
+    // 分组发生变化的病例, 新病例的old_drg为空
+    let mut delta_df = DataFrame::new(vec![
+        Series::new(
+            "结算流水号",
+            result
+                .delta
+                .iter()
+                .map(|d| d.id.clone())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "old_drg",
+            result
+                .delta
+                .iter()
+                .map(|d| d.old_drg.clone())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "new_drg",
+            result
+                .delta
+                .iter()
+                .map(|d| d.new_drg.clone())
+                .collect::<Vec<_>>(),
+        ),
+    ])?;
+
+    println!("data exporting ...");
+    for (path, frame) in [(out_path, &mut df), (delta_path, &mut delta_df)] {
+        CsvWriter::new(File::create(path)?)
+            .has_header(true)
+            .with_delimiter(b',')
+            .finish(frame)?;
+    }
+    // 导出成功后再更新存储
+    store.save(store_path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fixtures::{scheme, temp_path, write_sample_csv};
+
+    fn sample_csv(name: &str) -> String {
+        let path = temp_path(&format!("{}.csv", name));
+        write_sample_csv(&path);
+        path
+    }
+
+    #[test]
+    fn sensitivity_export_keeps_source_id() {
+        let in_path = sample_csv("sensitivity_in");
+        let out_path = temp_path("sensitivity_out.csv");
+        let summary_path = temp_path("sensitivity_summary.csv");
+        let scheme = scheme();
+        sensitivity_and_export(&in_path, &out_path, &summary_path, &scheme).unwrap();
+        let exported = std::fs::read_to_string(&out_path).unwrap();
+        for path in [&in_path, &out_path, &summary_path] {
+            std::fs::remove_file(path).unwrap();
+        }
+        let row = exported.lines().nth(1).unwrap();
+        assert!(row.starts_with("A001,"), "{}", row);
+    }
+
+    #[test]
+    fn missing_weight_is_an_error() {
+        let in_path = sample_csv("missing_weight");
+        let text = std::fs::read_to_string(&in_path).unwrap();
+        std::fs::write(&in_path, text.replace(",2789", ",")).unwrap();
+        let df = from_csv_file(&in_path).unwrap();
+        std::fs::remove_file(&in_path).unwrap();
+        let error = construct_drg_case(&df).err().unwrap().to_string();
+        assert!(error.contains("体重"), "{}", error);
+    }
+}
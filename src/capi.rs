@@ -0,0 +1,407 @@
+use crate::audit::format_findings;
+use crate::case::{icd_transform, DrgCase, Sex};
+use crate::group::{group_case, DrgResult};
+use crate::scheme::DrgScheme;
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::mem::{offset_of, size_of};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::string::String;
+
+// C接口版本, DrgCaseC、DrgResultC或函数签名不兼容地变化时递增
+pub const DRG_ABI_VERSION: u32 = 1;
+
+// 分组器句柄, 对C侧不透明
+pub struct DrgGrouper {
+    scheme: DrgScheme,
+}
+
+/// C结构体形式传入的病例, 字符串均为UTF-8且以'\0'结尾, 主手术可为NULL
+///
+/// weight及之前的字段必须填写; 之后的字段(以及以后追加在末尾的字段)由struct_size判断调用方是否包含,
+/// 不包含时视为未填写, 因此按旧头文件编译的调用方不需要重新编译
+#[repr(C)]
+pub struct DrgCaseC {
+    /// 调用方填写sizeof(DrgCaseC)
+    pub struct_size: usize,
+    pub id: *const c_char,
+    pub main_dis: *const c_char,
+    pub main_opt: *const c_char,
+    pub other_dis: *const *const c_char,
+    pub other_dis_len: usize,
+    pub other_opt: *const *const c_char,
+    pub other_opt_len: usize,
+    /// GB/T 2261.1编码(0 => 未知, 1 => 男, 2 => 女, 9 => 未说明)
+    pub sex: i64,
+    pub age: f64,
+    pub weight: i64,
+    /// 年龄不足1周岁天数, 小于0表示未填写
+    pub age_days: i64,
+    /// 新生儿出生体重(克), 小于0表示未填写
+    pub birth_weight: i64,
+    /// 新生儿入院体重(克), 小于0表示未填写
+    pub admission_weight: i64,
+    /// 有创呼吸机使用时间(小时), 小于0或NaN表示未填写
+    pub vent_hours: f64,
+}
+
+// 调用方至少需要提供的结构体大小, 即offsetof(DrgCaseC, age_days)
+const DRG_CASE_MIN_SIZE: usize = offset_of!(DrgCaseC, age_days);
+
+impl DrgCaseC {
+    // 可选字段均为未填写的病例, 用于补齐旧版本调用方没有的字段
+    fn unset() -> Self {
+        Self {
+            struct_size: size_of::<Self>(),
+            id: ptr::null(),
+            main_dis: ptr::null(),
+            main_opt: ptr::null(),
+            other_dis: ptr::null(),
+            other_dis_len: 0,
+            other_opt: ptr::null(),
+            other_opt_len: 0,
+            sex: 9,
+            age: 0.0,
+            weight: 0,
+            age_days: -1,
+            birth_weight: -1,
+            admission_weight: -1,
+            vent_hours: -1.0,
+        }
+    }
+}
+
+/// C结构体形式返回的分组结果, 需要使用drg_result_free释放
+///
+/// 以后增加的字段只追加在末尾
+#[repr(C)]
+pub struct DrgResultC {
+    pub id: *mut c_char,
+    pub mdc: *mut c_char,
+    pub adrg: *mut c_char,
+    pub drg: *mut c_char,
+    /// 分组说明(创伤部位、QY手术与CC/MCC), 每行一项, 以'\n'分隔
+    pub explain: *mut c_char,
+    /// 编码审核问题, 格式同批量导出的audit列(级别:规则:编码, 以';'分隔), 没有问题时为空字符串
+    pub audit: *mut c_char,
+}
+
+thread_local! {
+    // 当前线程最近一次调用的错误信息
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+// 执行闭包, 将错误与panic记录为最近一次错误, 失败时返回None
+fn guard<T, F: FnOnce() -> Result<T, String>>(f: F) -> Option<T> {
+    clear_last_error();
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(message)) => {
+            set_last_error(message);
+            None
+        }
+        Err(_) => {
+            set_last_error("drg grouping panicked".to_string());
+            None
+        }
+    }
+}
+
+// 读取C字符串, NULL视为空字符串
+unsafe fn read_str(s: *const c_char) -> Result<String, String> {
+    if s.is_null() {
+        return Ok(String::new());
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map(|s| s.trim().to_string())
+        .map_err(|e| e.to_string())
+}
+
+// 读取C字符串数组, 跳过空字符串
+unsafe fn read_str_array(items: *const *const c_char, len: usize) -> Result<Vec<String>, String> {
+    let mut vec: Vec<String> = Vec::new();
+    if items.is_null() {
+        return Ok(vec);
+    }
+    for i in 0..len {
+        let code = read_str(*items.add(i))?;
+        if !code.is_empty() {
+            vec.push(code);
+        }
+    }
+    Ok(vec)
+}
+
+// 可选的整数字段, 小于0表示未填写
+fn optional_int(value: i64) -> Option<i64> {
+    (value >= 0).then_some(value)
+}
+
+// 可选的浮点数字段, 小于0或NaN表示未填写
+fn optional_float(value: f64) -> Option<f64> {
+    (value >= 0.0).then_some(value)
+}
+
+fn to_c_string(s: &str) -> *mut c_char {
+    CString::new(s)
+        .map(CString::into_raw)
+        .unwrap_or(ptr::null_mut())
+}
+
+/// C接口版本, 调用方可以据此确认动态库与头文件是否匹配
+#[no_mangle]
+pub extern "C" fn drg_abi_version() -> u32 {
+    DRG_ABI_VERSION
+}
+
+/// 从分组方案目录创建分组器, 失败返回NULL, 错误信息见`drg_last_error`
+///
+/// # Safety
+/// `scheme_dir`必须是以'\0'结尾的UTF-8字符串
+#[no_mangle]
+pub unsafe extern "C" fn drg_grouper_new(scheme_dir: *const c_char) -> *mut DrgGrouper {
+    guard(|| {
+        let dir = read_str(scheme_dir)?;
+        let scheme = DrgScheme::from_dir(dir).map_err(|e| e.to_string())?;
+        Ok(Box::into_raw(Box::new(DrgGrouper { scheme })))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// 释放分组器
+///
+/// # Safety
+/// `grouper`必须是`drg_grouper_new`返回的指针或NULL, 且只能释放一次
+#[no_mangle]
+pub unsafe extern "C" fn drg_grouper_free(grouper: *mut DrgGrouper) {
+    if !grouper.is_null() {
+        drop(Box::from_raw(grouper));
+    }
+}
+
+/// 对C结构体形式的病例进行分组, 失败返回NULL
+///
+/// `struct_size`填写为`sizeof(DrgCaseC)`, 不小于`offsetof(DrgCaseC, age_days)`
+///
+/// # Safety
+/// `grouper`必须是有效的分组器, `record`中的字符串与数组指针在调用期间必须有效
+#[no_mangle]
+pub unsafe extern "C" fn drg_grouper_group(
+    grouper: *const DrgGrouper,
+    record: *const DrgCaseC,
+) -> *mut DrgResultC {
+    guard(|| {
+        if grouper.is_null() || record.is_null() {
+            return Err("grouper or case is NULL".to_string());
+        }
+        // 先只读取struct_size, 再复制调用方结构体包含的部分, 其余字段视为未填写
+        let struct_size = ptr::addr_of!((*record).struct_size).read();
+        if struct_size < DRG_CASE_MIN_SIZE {
+            return Err(format!(
+                "struct_size {} is smaller than the minimum DrgCaseC ({} bytes)",
+                struct_size, DRG_CASE_MIN_SIZE
+            ));
+        }
+        let mut c = DrgCaseC::unset();
+        ptr::copy_nonoverlapping(
+            record.cast::<u8>(),
+            ptr::addr_of_mut!(c).cast::<u8>(),
+            struct_size.min(size_of::<DrgCaseC>()),
+        );
+        let sex = Sex::from_code(c.sex).ok_or(format!("unknown sex code {}", c.sex))?;
+        let mut drg_case = DrgCase::new(
+            read_str(c.id)?,
+            icd_transform(read_str(c.main_dis)?),
+            read_str(c.main_opt)?,
+            read_str_array(c.other_dis, c.other_dis_len)?
+                .into_iter()
+                .map(icd_transform)
+                .collect(),
+            read_str_array(c.other_opt, c.other_opt_len)?,
+            sex,
+            c.age,
+            c.weight,
+        );
+        drg_case.age_days = optional_int(c.age_days);
+        drg_case.birth_weight = optional_int(c.birth_weight);
+        drg_case.admission_weight = optional_int(c.admission_weight);
+        drg_case.vent_hours = optional_float(c.vent_hours);
+        drg_case.concat_dis();
+        drg_case.concat_opt();
+        let result: DrgResult = group_case(&drg_case, &(*grouper).scheme);
+        Ok(Box::into_raw(Box::new(DrgResultC {
+            id: to_c_string(&result.id),
+            mdc: to_c_string(&result.mdc),
+            adrg: to_c_string(&result.adrg),
+            drg: to_c_string(&result.drg),
+            explain: to_c_string(&result.explain().join("\n")),
+            audit: to_c_string(&format_findings(&result.audit)),
+        })))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// 释放`drg_grouper_group`返回的分组结果
+///
+/// # Safety
+/// `result`必须是`drg_grouper_group`返回的指针或NULL, 且只能释放一次
+#[no_mangle]
+pub unsafe extern "C" fn drg_result_free(result: *mut DrgResultC) {
+    if result.is_null() {
+        return;
+    }
+    let result = Box::from_raw(result);
+    for s in [
+        result.id,
+        result.mdc,
+        result.adrg,
+        result.drg,
+        result.explain,
+        result.audit,
+    ] {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    }
+}
+
+/// 对JSON形式的病例进行分组, 返回JSON形式的分组结果, 失败返回NULL
+///
+/// 病例字段为id、main_dis、main_opt、other_dis、other_opt、sex、age、weight,
+/// 结果字段同管道模式的输出, 需要使用`drg_string_free`释放
+///
+/// # Safety
+/// `grouper`必须是有效的分组器, `case_json`必须是以'\0'结尾的UTF-8字符串
+#[no_mangle]
+pub unsafe extern "C" fn drg_grouper_group_json(
+    grouper: *const DrgGrouper,
+    case_json: *const c_char,
+) -> *mut c_char {
+    guard(|| {
+        if grouper.is_null() {
+            return Err("grouper is NULL".to_string());
+        }
+        let record = DrgCase::from_json(&read_str(case_json)?).map_err(|e| e.to_string())?;
+        let result = group_case(&record, &(*grouper).scheme);
+        let json = serde_json::to_string(&result).map_err(|e| e.to_string())?;
+        Ok(to_c_string(&json))
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// 释放本库返回的字符串
+///
+/// # Safety
+/// `s`必须是本库返回的字符串或NULL, 且只能释放一次
+#[no_mangle]
+pub unsafe extern "C" fn drg_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// 当前线程最近一次调用的错误信息, 没有错误时返回NULL
+///
+/// 返回的字符串由本库持有, 在本线程下一次调用前有效, 不需要释放
+#[no_mangle]
+pub extern "C" fn drg_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| match e.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neonate(id: &CString, main_dis: &CString) -> DrgCaseC {
+        DrgCaseC {
+            struct_size: std::mem::size_of::<DrgCaseC>(),
+            id: id.as_ptr(),
+            main_dis: main_dis.as_ptr(),
+            main_opt: ptr::null(),
+            other_dis: ptr::null(),
+            other_dis_len: 0,
+            other_opt: ptr::null(),
+            other_opt_len: 0,
+            sex: 1,
+            age: 0.0,
+            weight: 3000,
+            age_days: 3,
+            birth_weight: 1200,
+            admission_weight: -1,
+            vent_hours: -1.0,
+        }
+    }
+
+    #[test]
+    fn groups_neonate_by_birth_weight() {
+        let dir = CString::new("data").unwrap();
+        let (id, main_dis) = (CString::new("1").unwrap(), CString::new("P07.300").unwrap());
+        unsafe {
+            let grouper = drg_grouper_new(dir.as_ptr());
+            assert!(!grouper.is_null());
+            let result = drg_grouper_group(grouper, &neonate(&id, &main_dis));
+            assert!(!result.is_null());
+            assert_eq!(CStr::from_ptr((*result).adrg).to_str().unwrap(), "PS1");
+            drg_result_free(result);
+
+            // 旧头文件中较小的结构体, 缺少的字段视为未填写
+            let mut old = neonate(&id, &main_dis);
+            old.struct_size = offset_of!(DrgCaseC, birth_weight);
+            let result = drg_grouper_group(grouper, &old);
+            assert!(!result.is_null());
+            assert_ne!(CStr::from_ptr((*result).adrg).to_str().unwrap(), "PS1");
+            drg_result_free(result);
+
+            let mut truncated = neonate(&id, &main_dis);
+            truncated.struct_size = DRG_CASE_MIN_SIZE - 1;
+            assert!(drg_grouper_group(grouper, &truncated).is_null());
+            assert!(!drg_last_error().is_null());
+            drg_grouper_free(grouper);
+        }
+    }
+
+    #[test]
+    fn result_carries_explanation_and_audit() {
+        let dir = CString::new("data").unwrap();
+        let id = CString::new("2").unwrap();
+        let main_dis = CString::new("J20.900").unwrap();
+        let other = [CString::new("E87.102").unwrap()];
+        let other_ptrs: Vec<*const c_char> = other.iter().map(|s| s.as_ptr()).collect();
+        let opt = CString::new("39.9014").unwrap();
+        let mut record = DrgCaseC::unset();
+        record.id = id.as_ptr();
+        record.main_dis = main_dis.as_ptr();
+        record.main_opt = opt.as_ptr();
+        record.other_dis = other_ptrs.as_ptr();
+        record.other_dis_len = other_ptrs.len();
+        record.sex = 1;
+        record.age = 30.0;
+        unsafe {
+            let grouper = drg_grouper_new(dir.as_ptr());
+            let result = drg_grouper_group(grouper, &record);
+            assert!(!result.is_null());
+            assert_eq!(CStr::from_ptr((*result).drg).to_str().unwrap(), "EQY");
+            let explain = CStr::from_ptr((*result).explain).to_str().unwrap();
+            assert!(explain.contains("QY operation: 39.9014"), "{}", explain);
+            assert!(explain.contains("E87.102 CC"), "{}", explain);
+            let audit = CStr::from_ptr((*result).audit).to_str().unwrap();
+            assert!(audit.contains("39.9014"), "{}", audit);
+            drg_result_free(result);
+            drg_grouper_free(grouper);
+        }
+    }
+}
@@ -0,0 +1,973 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::string::String;
+
+// 表格中其他诊断编码列
+pub const OTHER_DIS_COLUMNS: [&str; 16] = [
+    "其他诊断编码1",
+    "其他诊断编码2",
+    "其他诊断编码3",
+    "其他诊断编码4",
+    "其他诊断编码5",
+    "其他诊断编码6",
+    "其他诊断编码7",
+    "其他诊断编码8",
+    "其他诊断编码9",
+    "其他诊断编码10",
+    "其他诊断编码11",
+    "其他诊断编码12",
+    "其他诊断编码13",
+    "其他诊断编码14",
+    "其他诊断编码15",
+    "其他诊断编码16",
+];
+
+// 表格中其他手术编码列
+pub const OTHER_OPT_COLUMNS: [&str; 16] = [
+    "其他手术编码1",
+    "其他手术编码2",
+    "其他手术编码3",
+    "其他手术编码4",
+    "其他手术编码5",
+    "其他手术编码6",
+    "其他手术编码7",
+    "其他手术编码8",
+    "其他手术编码9",
+    "其他手术编码10",
+    "其他手术编码11",
+    "其他手术编码12",
+    "其他手术编码13",
+    "其他手术编码14",
+    "其他手术编码15",
+    "其他手术编码16",
+];
+
+// 转换ICD编码, 在ICD10中涉及到x的只有小写
+pub fn icd_transform(icd: String) -> String {
+    icd.chars()
+        .map(|c| {
+            if c == 'x' {
+                c
+            } else {
+                c.to_uppercase().collect::<String>().chars().next().unwrap()
+            }
+        })
+        .collect::<String>()
+}
+
+// 解析日期, 支持常见的日期与日期时间格式
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim().trim_matches('\"');
+    for fmt in ["%Y-%m-%d", "%Y/%m/%d", "%Y%m%d", "%Y.%m.%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, fmt) {
+            return Some(date);
+        }
+    }
+    for fmt in [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(value, fmt) {
+            return Some(datetime.date());
+        }
+    }
+    None
+}
+
+// 由出生日期与入院日期计算周岁年龄与出生天数, 出生日期晚于入院日期时返回None
+pub fn age_from_dates(birth: NaiveDate, admission: NaiveDate) -> Option<(i64, i64)> {
+    if birth > admission {
+        return None;
+    }
+    let mut years = (admission.year() - birth.year()) as i64;
+    if (admission.month(), admission.day()) < (birth.month(), birth.day()) {
+        years -= 1;
+    }
+    Some((years, (admission - birth).num_days()))
+}
+
+// 性别==============================================================================================
+// 性别, 编码遵循GB/T 2261.1(0 => 未知, 1 => 男, 2 => 女, 9 => 未说明)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SexValue", into = "i64")]
+pub enum Sex {
+    Male,    // 男
+    Female,  // 女
+    Unknown, // 未知或未说明
+}
+
+// JSON中的性别可以是编码数字或文本
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SexValue {
+    Code(i64),
+    Text(String),
+}
+
+impl Sex {
+    // 从GB/T 2261.1编码解析
+    pub fn from_code(code: i64) -> Option<Self> {
+        match code {
+            1 => Some(Self::Male),
+            2 => Some(Self::Female),
+            0 | 9 => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
+    // 从编码或文本(男/女、M/F、male/female、未知/未说明)解析, 空白视为未知
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().trim_matches('\"');
+        if let Ok(code) = value.parse::<f64>() {
+            return if code.fract() == 0.0 {
+                Self::from_code(code as i64)
+            } else {
+                None
+            };
+        }
+        match value.to_lowercase().as_str() {
+            "男" | "m" | "male" => Some(Self::Male),
+            "女" | "f" | "female" => Some(Self::Female),
+            "" | "未知" | "未说明" | "未说明的性别" | "u" | "unknown" => {
+                Some(Self::Unknown)
+            }
+            _ => None,
+        }
+    }
+
+    // GB/T 2261.1编码
+    pub fn code(&self) -> i64 {
+        match self {
+            Self::Male => 1,
+            Self::Female => 2,
+            Self::Unknown => 9,
+        }
+    }
+}
+
+impl TryFrom<SexValue> for Sex {
+    type Error = String;
+    fn try_from(value: SexValue) -> Result<Self, Self::Error> {
+        match value {
+            SexValue::Code(code) => {
+                Self::from_code(code).ok_or(format!("unknown sex code {}", code))
+            }
+            SexValue::Text(text) => Self::parse(&text).ok_or(format!("unknown sex value {}", text)),
+        }
+    }
+}
+
+impl From<Sex> for i64 {
+    fn from(value: Sex) -> Self {
+        value.code()
+    }
+}
+
+// 结算清单字段=======================================================================================
+// 离院方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum DischargeDisposition {
+    Ordered,         // 1 医嘱离院
+    Transferred,     // 2 医嘱转院
+    CommunityHealth, // 3 医嘱转社区卫生服务机构/乡镇卫生院
+    AgainstAdvice,   // 4 非医嘱离院
+    Death,           // 5 死亡
+    Other,           // 9 其他
+}
+
+impl DischargeDisposition {
+    // 从编码或中文名称解析
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().trim_matches('\"') {
+            "1" | "医嘱离院" => Some(Self::Ordered),
+            "2" | "医嘱转院" => Some(Self::Transferred),
+            "3" | "医嘱转社区卫生服务机构/乡镇卫生院" | "医嘱转社区" => {
+                Some(Self::CommunityHealth)
+            }
+            "4" | "非医嘱离院" => Some(Self::AgainstAdvice),
+            "5" | "死亡" => Some(Self::Death),
+            "9" | "其他" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    // 结算清单编码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Ordered => "1",
+            Self::Transferred => "2",
+            Self::CommunityHealth => "3",
+            Self::AgainstAdvice => "4",
+            Self::Death => "5",
+            Self::Other => "9",
+        }
+    }
+}
+
+impl TryFrom<String> for DischargeDisposition {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value).ok_or(format!("unknown discharge disposition {}", value))
+    }
+}
+
+impl From<DischargeDisposition> for String {
+    fn from(value: DischargeDisposition) -> Self {
+        value.code().to_string()
+    }
+}
+
+// 入院途径
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AdmissionRoute {
+    Emergency,  // 1 急诊
+    Outpatient, // 2 门诊
+    Transfer,   // 3 其他医疗机构转入
+    Other,      // 9 其他
+}
+
+impl AdmissionRoute {
+    // 从编码或中文名称解析
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().trim_matches('\"') {
+            "1" | "急诊" => Some(Self::Emergency),
+            "2" | "门诊" => Some(Self::Outpatient),
+            "3" | "其他医疗机构转入" => Some(Self::Transfer),
+            "9" | "其他" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    // 结算清单编码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Emergency => "1",
+            Self::Outpatient => "2",
+            Self::Transfer => "3",
+            Self::Other => "9",
+        }
+    }
+}
+
+impl TryFrom<String> for AdmissionRoute {
+    type Error = String;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value).ok_or(format!("unknown admission route {}", value))
+    }
+}
+
+impl From<AdmissionRoute> for String {
+    fn from(value: AdmissionRoute) -> Self {
+        value.code().to_string()
+    }
+}
+
+// 住院费用(元), 分类与结算清单一致
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaseCost {
+    pub total: Option<f64>,            // 总费用
+    pub bed: Option<f64>,              // 床位费
+    pub consultation: Option<f64>,     // 诊察费
+    pub examination: Option<f64>,      // 检查费
+    pub laboratory: Option<f64>,       // 化验费
+    pub treatment: Option<f64>,        // 治疗费
+    pub surgery: Option<f64>,          // 手术费
+    pub nursing: Option<f64>,          // 护理费
+    pub material: Option<f64>,         // 卫生材料费
+    pub western_medicine: Option<f64>, // 西药费
+    pub herbal_medicine: Option<f64>,  // 中药饮片费
+    pub patent_medicine: Option<f64>,  // 中成药费
+    pub general_service: Option<f64>,  // 一般诊疗费
+    pub registration: Option<f64>,     // 挂号费
+    pub other: Option<f64>,            // 其他费
+}
+
+// 费用分类对应的表格列名
+pub const COST_COLUMNS: [&str; 15] = [
+    "总费用",
+    "床位费",
+    "诊察费",
+    "检查费",
+    "化验费",
+    "治疗费",
+    "手术费",
+    "护理费",
+    "卫生材料费",
+    "西药费",
+    "中药饮片费",
+    "中成药费",
+    "一般诊疗费",
+    "挂号费",
+    "其他费",
+];
+
+impl CaseCost {
+    // 按COST_COLUMNS的顺序取得各分类费用的引用
+    pub fn fields(&self) -> [&Option<f64>; 15] {
+        [
+            &self.total,
+            &self.bed,
+            &self.consultation,
+            &self.examination,
+            &self.laboratory,
+            &self.treatment,
+            &self.surgery,
+            &self.nursing,
+            &self.material,
+            &self.western_medicine,
+            &self.herbal_medicine,
+            &self.patent_medicine,
+            &self.general_service,
+            &self.registration,
+            &self.other,
+        ]
+    }
+
+    // 按COST_COLUMNS的顺序取得各分类费用的可变引用
+    pub fn fields_mut(&mut self) -> [&mut Option<f64>; 15] {
+        [
+            &mut self.total,
+            &mut self.bed,
+            &mut self.consultation,
+            &mut self.examination,
+            &mut self.laboratory,
+            &mut self.treatment,
+            &mut self.surgery,
+            &mut self.nursing,
+            &mut self.material,
+            &mut self.western_medicine,
+            &mut self.herbal_medicine,
+            &mut self.patent_medicine,
+            &mut self.general_service,
+            &mut self.registration,
+            &mut self.other,
+        ]
+    }
+
+    // 总费用, 未填写时为各分类费用之和(均未填写时为None)
+    pub fn total_cost(&self) -> Option<f64> {
+        if self.total.is_some() {
+            return self.total;
+        }
+        self.fields()[1..]
+            .iter()
+            .filter_map(|v| **v)
+            .reduce(|a, b| a + b)
+    }
+}
+
+// 表格行读取=========================================================================================
+// 读取一行病例数据时的错误
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowError {
+    Missing(String), // 缺少必填字段(字段名)
+    Invalid(String), // 字段值无效(说明)
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(key) => write!(f, "{} is missing", key),
+            Self::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for RowError {}
+
+// 按列名读取一行病例数据, 表格批量分组与Python绑定共用同一套字段规则(见DrgCase::from_row)
+pub trait CaseRow {
+    // 文本值, 去除首尾空白, 缺失、空值与空白返回None
+    fn text(&self, key: &str) -> Result<Option<String>, RowError>;
+    // 数值, 缺失、空值与NaN返回None
+    fn number(&self, key: &str) -> Result<Option<f64>, RowError>;
+}
+
+// 病例结构===========================================================================================
+// 以JSON传入时字段名与结构体一致, 反序列化后需调用normalize
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrgCase {
+    pub id: String,       // 病例ID
+    pub main_dis: String, // 主诊断编码(必填)
+    #[serde(default)]
+    pub main_opt: String, // 主手术编码(手术病例必填)
+    #[serde(default)]
+    pub other_dis: Vec<String>, // 其他诊断编码(列表)
+    #[serde(default)]
+    pub other_opt: Vec<String>, // 其他手术编码(列表)
+    pub sex: Sex,         // 性别
+    pub age: f64,         // 年龄(不足一岁以小于1小数表示, 出生天数/365)
+    pub weight: i64,      // 体重
+    #[serde(default)]
+    pub age_days: Option<i64>, // 年龄不足1周岁天数
+    #[serde(default)]
+    pub birth_weight: Option<i64>, // 新生儿出生体重(克)
+    #[serde(default)]
+    pub admission_weight: Option<i64>, // 新生儿入院体重(克)
+    #[serde(default)]
+    pub vent_hours: Option<f64>, // 有创呼吸机使用时间(小时)
+    #[serde(default)]
+    pub birth_date: Option<NaiveDate>, // 出生日期
+    #[serde(default)]
+    pub admission_date: Option<NaiveDate>, // 入院日期
+    #[serde(default)]
+    pub discharge_date: Option<NaiveDate>, // 出院日期
+    #[serde(default)]
+    pub los: Option<i64>, // 实际住院天数
+    #[serde(default)]
+    pub discharge_disposition: Option<DischargeDisposition>, // 离院方式
+    #[serde(default)]
+    pub admission_route: Option<AdmissionRoute>, // 入院途径
+    #[serde(default)]
+    pub department: Option<String>, // 出院科别
+    #[serde(default)]
+    pub hospital_code: Option<String>, // 定点医疗机构代码
+    #[serde(default)]
+    pub cost: CaseCost, // 住院费用
+    #[serde(skip)]
+    pub all_dis: HashSet<String>, // 所有的诊断
+    #[serde(skip)]
+    pub all_opt: HashSet<String>, // 所有的手术
+}
+
+impl DrgCase {
+    // 初始化方法
+    pub fn new(
+        admission_number: String,
+        principal_diagnosis: String,
+        principal_operation: String,
+        other_diagnosis: Vec<String>,
+        other_operation: Vec<String>,
+        gender: Sex,
+        old: f64,
+        mass: i64,
+    ) -> Self {
+        Self {
+            id: admission_number,
+            main_dis: principal_diagnosis,
+            main_opt: principal_operation,
+            other_dis: other_diagnosis,
+            other_opt: other_operation,
+            sex: gender,
+            age: old,
+            weight: mass,
+            age_days: None,
+            birth_weight: None,
+            admission_weight: None,
+            vent_hours: None,
+            birth_date: None,
+            admission_date: None,
+            discharge_date: None,
+            los: None,
+            discharge_disposition: None,
+            admission_route: None,
+            department: None,
+            hospital_code: None,
+            cost: CaseCost::default(),
+            all_dis: HashSet::new(), // 初始化为空
+            all_opt: HashSet::new(), // 初始化为空
+        }
+    }
+
+    // 年龄天数, 优先使用年龄不足1周岁天数, 否则由不足一岁的小数年龄换算
+    // 满一岁返回None, 此时忽略填写的年龄不足1周岁天数
+    pub fn age_in_days(&self) -> Option<i64> {
+        if self.age >= 1.0 {
+            return None;
+        }
+        Some(
+            self.age_days
+                .unwrap_or_else(|| (self.age * 365.0).round() as i64),
+        )
+    }
+
+    // 由出生日期与入院日期填写年龄, 不足一岁且未填写年龄不足1周岁天数时一并填写, 缺少日期时返回false
+    pub fn fill_age_from_dates(&mut self) -> bool {
+        let (years, days) = match (self.birth_date, self.admission_date) {
+            (Some(birth), Some(admission)) => match age_from_dates(birth, admission) {
+                Some(age) => age,
+                None => return false,
+            },
+            _ => return false,
+        };
+        if years < 1 {
+            self.age = days as f64 / 365.0;
+            self.age_days.get_or_insert(days);
+        } else {
+            self.age = years as f64;
+        }
+        true
+    }
+
+    // 核对填写的年龄与出生日期、入院日期是否一致, 不一致时返回说明
+    pub fn age_mismatch(&self) -> Option<String> {
+        let (years, days) = age_from_dates(self.birth_date?, self.admission_date?)?;
+        if years >= 1 && self.age.floor() as i64 != years {
+            return Some(format!(
+                "age {} does not match {} years derived from dates",
+                self.age, years
+            ));
+        }
+        if years < 1 {
+            if self.age >= 1.0 {
+                return Some(format!(
+                    "age {} does not match {} days derived from dates",
+                    self.age, days
+                ));
+            }
+            // 由小数年龄换算的天数允许1天的舍入误差
+            let tolerance = if self.age_days.is_some() { 0 } else { 1 };
+            let age_days = self.age_in_days()?;
+            if (age_days - days).abs() > tolerance {
+                return Some(format!(
+                    "age in days {} does not match {} days derived from dates",
+                    age_days, days
+                ));
+            }
+        }
+        None
+    }
+
+    // 新生儿分组使用的体重, 优先出生体重, 其次入院体重, 最后为体重
+    pub fn neonatal_weight(&self) -> i64 {
+        self.birth_weight
+            .or(self.admission_weight)
+            .unwrap_or(self.weight)
+    }
+
+    // 住院天数, 优先使用实际住院天数, 否则由出入院日期计算(当天出入院按1天), 出院早于入院时为None
+    pub fn length_of_stay(&self) -> Option<i64> {
+        match (self.los, self.admission_date, self.discharge_date) {
+            (Some(los), _, _) => Some(los),
+            (None, Some(admission), Some(discharge)) if discharge >= admission => {
+                Some((discharge - admission).num_days().max(1))
+            }
+            _ => None,
+        }
+    }
+
+    // 检查病例是否有主手术
+    pub fn no_surgery(&self) -> bool {
+        return self.main_opt.is_empty();
+    }
+
+    // 检查病例是否有其他手术
+    pub fn no_other_surgery(&self) -> bool {
+        return self.other_opt.is_empty();
+    }
+
+    // 检查病例是否有其他诊断
+    pub fn no_other_diagnosis(&self) -> bool {
+        return self.other_dis.is_empty();
+    }
+
+    // 合并主诊断与其他诊断为一个set
+    pub fn concat_dis(&mut self) {
+        let mut temp_dis = self.other_dis.clone();
+        let principle_dis = self.main_dis.clone();
+        self.all_dis.insert(principle_dis);
+        temp_dis.retain(|r| self.all_dis.insert(r.to_string()))
+    }
+
+    // 从一行表格数据构造病例, 字段名为批量分组表格的列名
+    pub fn from_row<R: CaseRow>(row: &R) -> Result<Self, RowError> {
+        let id = row
+            .text("结算流水号")?
+            .ok_or_else(|| RowError::Missing("结算流水号".to_string()))?;
+        let main_dis = row
+            .text("主诊断编码")?
+            .map(icd_transform)
+            .ok_or_else(|| RowError::Missing("主诊断编码".to_string()))?;
+        let mut other_dis: Vec<String> = Vec::new();
+        for colname in OTHER_DIS_COLUMNS {
+            other_dis.extend(row.text(colname)?.map(icd_transform));
+        }
+        let mut other_opt: Vec<String> = Vec::new();
+        for colname in OTHER_OPT_COLUMNS {
+            other_opt.extend(row.text(colname)?);
+        }
+        // 性别可能是编码或文本, 空值为未知
+        let sex_value = row.text("性别")?.unwrap_or_default();
+        let sex = Sex::parse(&sex_value)
+            .ok_or_else(|| RowError::Invalid(format!("unknown sex value {}", sex_value)))?;
+        let weight = match row.number("体重")? {
+            Some(w) if w.is_finite() => w as i64,
+            Some(w) => return Err(RowError::Invalid(format!("体重 {} is not a number", w))),
+            None => return Err(RowError::Missing("体重".to_string())),
+        };
+        // 年龄可以缺失, 此时由出生日期与入院日期计算
+        let age = row.number("年龄")?;
+        let mut drg_case = DrgCase::new(
+            id,
+            main_dis,
+            row.text("主手术编码")?.unwrap_or_default(),
+            other_dis,
+            other_opt,
+            sex,
+            age.unwrap_or(0.0),
+            weight,
+        );
+        let int =
+            |key: &str| -> Result<Option<i64>, RowError> { Ok(row.number(key)?.map(|v| v as i64)) };
+        // 填写了但无法解析的日期、离院方式与入院途径视为无效
+        let date = |key: &str| -> Result<Option<NaiveDate>, RowError> {
+            match row.text(key)? {
+                Some(value) => parse_date(&value)
+                    .map(Some)
+                    .ok_or_else(|| RowError::Invalid(format!("{} {} is not a date", key, value))),
+                None => Ok(None),
+            }
+        };
+        // 新生儿相关字段与有创呼吸机使用时间(小时)
+        drg_case.age_days = int("年龄不足1周岁天数")?;
+        drg_case.birth_weight = int("新生儿出生体重")?;
+        drg_case.admission_weight = int("新生儿入院体重")?;
+        drg_case.vent_hours = row.number("呼吸机使用时间")?;
+        // 结算清单中的其他可选字段
+        drg_case.birth_date = date("出生日期")?;
+        drg_case.admission_date = date("入院日期")?;
+        drg_case.discharge_date = date("出院日期")?;
+        drg_case.los = int("实际住院天数")?;
+        drg_case.discharge_disposition = match row.text("离院方式")? {
+            Some(value) => Some(DischargeDisposition::try_from(value).map_err(RowError::Invalid)?),
+            None => None,
+        };
+        drg_case.admission_route = match row.text("入院途径")? {
+            Some(value) => Some(AdmissionRoute::try_from(value).map_err(RowError::Invalid)?),
+            None => None,
+        };
+        drg_case.department = row.text("出院科别")?;
+        drg_case.hospital_code = row.text("定点医疗机构代码")?;
+        for (field, colname) in drg_case.cost.fields_mut().into_iter().zip(COST_COLUMNS) {
+            *field = row.number(colname)?;
+        }
+        // 没有年龄时由日期计算, 填写的年龄与日期不一致时由审核规则age_dates给出
+        if age.is_none() && !drg_case.fill_age_from_dates() {
+            return Err(RowError::Missing("年龄".to_string()));
+        }
+        drg_case.concat_dis(); // 将其他诊断与主诊断合并在一起
+        drg_case.concat_opt(); // 将其他手术与主手术合并在一起
+        Ok(drg_case)
+    }
+
+    // 从一行JSON构造病例
+    pub fn from_json(line: &str) -> Result<Self, serde_json::Error> {
+        let mut drg_case: DrgCase = serde_json::from_str(line)?;
+        drg_case.normalize();
+        Ok(drg_case)
+    }
+
+    // 统一编码格式(去除空白, 诊断编码转换大小写)并合并诊断与手术
+    pub fn normalize(&mut self) {
+        self.main_dis = icd_transform(self.main_dis.trim().to_string());
+        self.main_opt = self.main_opt.trim().to_string();
+        self.other_dis = self
+            .other_dis
+            .iter()
+            .map(|d| icd_transform(d.trim().to_string()))
+            .filter(|d| !d.is_empty())
+            .collect();
+        self.other_opt = self
+            .other_opt
+            .iter()
+            .map(|o| o.trim().to_string())
+            .filter(|o| !o.is_empty())
+            .collect();
+        self.recombine();
+    }
+
+    // 以某个其他诊断为主诊断、某个其他手术为主手术的病例, 原主诊断与主手术放回其他诊断与其他手术中的相同位置
+    pub fn with_principal(&self, dis_idx: Option<usize>, opt_idx: Option<usize>) -> Self {
+        let mut drg_case = self.clone();
+        if let Some(idx) = dis_idx {
+            std::mem::swap(&mut drg_case.main_dis, &mut drg_case.other_dis[idx]);
+        }
+        if let Some(idx) = opt_idx {
+            std::mem::swap(&mut drg_case.main_opt, &mut drg_case.other_opt[idx]);
+            // 原来没有主手术时不保留空的其他手术
+            drg_case.other_opt.retain(|o| !o.is_empty());
+        }
+        drg_case.recombine();
+        drg_case
+    }
+
+    // 修改诊断或手术后重新合并
+    pub fn recombine(&mut self) {
+        self.all_dis.clear();
+        self.all_opt.clear();
+        self.concat_dis();
+        self.concat_opt();
+    }
+
+    // 合并主诊断与其他诊断为一个set
+    pub fn concat_opt(&mut self) {
+        match self.main_opt.as_str() {
+            "" => (),
+            _ => {
+                let mut temp_opt = self.other_opt.clone();
+                let principle_opt = self.main_opt.clone();
+                self.all_opt.insert(principle_opt);
+                temp_opt.retain(|r| self.all_opt.insert(r.to_string()))
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::sample_case;
+    use std::collections::HashMap;
+
+    fn case_with_age(age: f64, age_days: Option<i64>) -> DrgCase {
+        let mut case = sample_case("A04.402", &[], &[], age);
+        case.age_days = age_days;
+        case
+    }
+
+    // 以HashMap表示的一行数据
+    struct MapRow(HashMap<&'static str, &'static str>);
+
+    impl CaseRow for MapRow {
+        fn text(&self, key: &str) -> Result<Option<String>, RowError> {
+            Ok(self.0.get(key).map(|v| v.to_string()))
+        }
+
+        fn number(&self, key: &str) -> Result<Option<f64>, RowError> {
+            match self.0.get(key) {
+                Some(v) => v
+                    .parse::<f64>()
+                    .map(|n| Some(n).filter(|n| !n.is_nan()))
+                    .map_err(|_| RowError::Invalid(format!("{} must be a number", key))),
+                None => Ok(None),
+            }
+        }
+    }
+
+    fn sample_row(weight: &'static str) -> MapRow {
+        MapRow(HashMap::from([
+            ("结算流水号", "A001"),
+            ("主诊断编码", "j20.900"),
+            ("其他诊断编码2", "E87.102"),
+            ("性别", "男"),
+            ("年龄", "29"),
+            ("体重", weight),
+        ]))
+    }
+
+    #[test]
+    fn case_from_row() {
+        let case = DrgCase::from_row(&sample_row("2789")).unwrap();
+        assert_eq!(case.id, "A001");
+        assert_eq!(case.main_dis, "J20.900");
+        assert_eq!(case.other_dis, vec!["E87.102".to_string()]);
+        assert_eq!(case.sex, Sex::Male);
+        assert_eq!(case.weight, 2789);
+        assert!(case.all_dis.contains("E87.102"));
+    }
+
+    #[test]
+    fn case_from_row_rejects_bad_weight() {
+        assert_eq!(
+            DrgCase::from_row(&sample_row("NaN")).err(),
+            Some(RowError::Missing("体重".to_string()))
+        );
+        assert!(matches!(
+            DrgCase::from_row(&sample_row("inf")),
+            Err(RowError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn age_in_days_for_neonates() {
+        assert_eq!(case_with_age(0.0, Some(0)).age_in_days(), Some(0));
+        assert_eq!(case_with_age(0.05, Some(20)).age_in_days(), Some(20));
+        assert_eq!(case_with_age(28.0 / 365.0, None).age_in_days(), Some(28));
+        assert_eq!(case_with_age(0.99, None).age_in_days(), Some(361));
+    }
+
+    #[test]
+    fn age_in_days_ignored_for_adults() {
+        assert_eq!(case_with_age(1.0, None).age_in_days(), None);
+        assert_eq!(case_with_age(1.0, Some(0)).age_in_days(), None);
+        assert_eq!(case_with_age(45.0, Some(0)).age_in_days(), None);
+    }
+
+    #[test]
+    fn sex_from_code_follows_gb_t_2261_1() {
+        assert_eq!(Sex::from_code(0), Some(Sex::Unknown));
+        assert_eq!(Sex::from_code(1), Some(Sex::Male));
+        assert_eq!(Sex::from_code(2), Some(Sex::Female));
+        assert_eq!(Sex::from_code(9), Some(Sex::Unknown));
+        assert_eq!(Sex::from_code(3), None);
+    }
+
+    #[test]
+    fn sex_parse_accepts_codes_and_text() {
+        assert_eq!(Sex::parse("0"), Some(Sex::Unknown));
+        assert_eq!(Sex::parse("1.0"), Some(Sex::Male));
+        assert_eq!(Sex::parse("\"2\""), Some(Sex::Female));
+        assert_eq!(Sex::parse("1.5"), None);
+        assert_eq!(Sex::parse(" 男 "), Some(Sex::Male));
+        assert_eq!(Sex::parse("F"), Some(Sex::Female));
+        assert_eq!(Sex::parse("female"), Some(Sex::Female));
+        assert_eq!(Sex::parse(""), Some(Sex::Unknown));
+        assert_eq!(Sex::parse("未说明"), Some(Sex::Unknown));
+        assert_eq!(Sex::parse("x"), None);
+    }
+
+    #[test]
+    fn sex_json_round_trip() {
+        let sex: Sex = serde_json::from_str("0").unwrap();
+        assert_eq!(sex, Sex::Unknown);
+        let sex: Sex = serde_json::from_str("\"女\"").unwrap();
+        assert_eq!(sex, Sex::Female);
+        assert!(serde_json::from_str::<Sex>("5").is_err());
+        assert_eq!(serde_json::to_string(&Sex::Female).unwrap(), "2");
+    }
+
+    #[test]
+    fn age_from_dates_counts_full_years() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(
+            age_from_dates(date(2020, 3, 1), date(2020, 3, 1)),
+            Some((0, 0))
+        );
+        assert_eq!(
+            age_from_dates(date(2020, 3, 1), date(2021, 2, 28)),
+            Some((0, 364))
+        );
+        assert_eq!(
+            age_from_dates(date(2020, 3, 1), date(2021, 3, 1)),
+            Some((1, 365))
+        );
+        assert_eq!(
+            age_from_dates(date(1980, 6, 15), date(2024, 6, 14))
+                .unwrap()
+                .0,
+            43
+        );
+        assert_eq!(
+            age_from_dates(date(1980, 6, 15), date(2024, 6, 15))
+                .unwrap()
+                .0,
+            44
+        );
+        // 闰日出生者在平年3月1日满周岁
+        assert_eq!(
+            age_from_dates(date(2020, 2, 29), date(2021, 2, 28))
+                .unwrap()
+                .0,
+            0
+        );
+        assert_eq!(
+            age_from_dates(date(2020, 2, 29), date(2021, 3, 1))
+                .unwrap()
+                .0,
+            1
+        );
+        assert_eq!(age_from_dates(date(2021, 1, 2), date(2021, 1, 1)), None);
+    }
+
+    fn row_with(fields: &[(&'static str, &'static str)]) -> MapRow {
+        let mut row = sample_row("2789");
+        row.0.extend(fields.iter().copied());
+        row
+    }
+
+    #[test]
+    fn settlement_fields_from_row() {
+        let case = DrgCase::from_row(&row_with(&[
+            ("入院日期", "2024/01/30"),
+            ("出院日期", "20240205"),
+            ("离院方式", "医嘱转院"),
+            ("入院途径", "1"),
+            ("出院科别", "呼吸内科"),
+            ("定点医疗机构代码", "H0001"),
+            ("床位费", "300"),
+            ("西药费", "1200.5"),
+        ]))
+        .unwrap();
+        assert_eq!(case.admission_date, NaiveDate::from_ymd_opt(2024, 1, 30));
+        assert_eq!(case.discharge_date, NaiveDate::from_ymd_opt(2024, 2, 5));
+        assert_eq!(case.length_of_stay(), Some(6));
+        assert_eq!(
+            case.discharge_disposition,
+            Some(DischargeDisposition::Transferred)
+        );
+        assert_eq!(case.admission_route, Some(AdmissionRoute::Emergency));
+        assert_eq!(case.department.as_deref(), Some("呼吸内科"));
+        assert_eq!(case.hospital_code.as_deref(), Some("H0001"));
+        assert_eq!(case.cost.bed, Some(300.0));
+        assert_eq!(case.cost.total_cost(), Some(1500.5));
+    }
+
+    #[test]
+    fn settlement_fields_absent() {
+        let case = DrgCase::from_row(&sample_row("2789")).unwrap();
+        assert_eq!(case.admission_date, None);
+        assert_eq!(case.length_of_stay(), None);
+        assert_eq!(case.discharge_disposition, None);
+        assert_eq!(case.admission_route, None);
+        assert_eq!(case.cost, CaseCost::default());
+        assert_eq!(case.cost.total_cost(), None);
+    }
+
+    #[test]
+    fn settlement_fields_malformed() {
+        for field in [
+            ("入院日期", "2024-13-01"),
+            ("出院日期", "yesterday"),
+            ("离院方式", "6"),
+            ("入院途径", "步行"),
+            ("总费用", "abc"),
+        ] {
+            assert!(
+                matches!(
+                    DrgCase::from_row(&row_with(&[field])),
+                    Err(RowError::Invalid(_))
+                ),
+                "{:?}",
+                field
+            );
+        }
+    }
+
+    #[test]
+    fn length_of_stay_from_dates() {
+        let case = DrgCase::from_row(&row_with(&[
+            ("入院日期", "2024-02-05"),
+            ("出院日期", "2024-02-01"),
+        ]))
+        .unwrap();
+        // 出院早于入院
+        assert_eq!(case.length_of_stay(), None);
+
+        let mut case = DrgCase::from_row(&row_with(&[
+            ("入院日期", "2024-02-05"),
+            ("出院日期", "2024-02-05"),
+        ]))
+        .unwrap();
+        assert_eq!(case.length_of_stay(), Some(1));
+        // 实际住院天数优先
+        case.los = Some(3);
+        assert_eq!(case.length_of_stay(), Some(3));
+    }
+
+    #[test]
+    fn total_cost_prefers_reported_total() {
+        let mut cost = CaseCost {
+            bed: Some(100.0),
+            nursing: Some(50.0),
+            ..Default::default()
+        };
+        assert_eq!(cost.total_cost(), Some(150.0));
+        cost.total = Some(160.0);
+        assert_eq!(cost.total_cost(), Some(160.0));
+    }
+}
@@ -0,0 +1,97 @@
+// 各模块测试共用的分组方案、病例与输入文件
+use crate::case::{DrgCase, Sex};
+#[cfg(any(feature = "batch", feature = "sqlite"))]
+use crate::case::{OTHER_DIS_COLUMNS, OTHER_OPT_COLUMNS};
+use crate::scheme::{DrgScheme, OPTIONAL_SCHEME_FILES, SCHEME_FILES};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// 随附的分组方案(data目录)
+pub fn scheme() -> DrgScheme {
+    DrgScheme::from_dir("data").unwrap()
+}
+
+// data目录中的全部方案文件, 用于修改个别文件后以from_bytes构造方案
+pub fn scheme_files() -> HashMap<String, Vec<u8>> {
+    let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+    for name in SCHEME_FILES.iter().chain(OPTIONAL_SCHEME_FILES.iter()) {
+        if let Ok(bytes) = fs::read(Path::new("data").join(name)) {
+            files.insert(name.to_string(), bytes);
+        }
+    }
+    files
+}
+
+// 男性病例(ID为1, 体重3000克), opts中的第一个手术为主手术
+pub fn sample_case(main_dis: &str, other_dis: &[&str], opts: &[&str], age: f64) -> DrgCase {
+    let mut case = DrgCase::new(
+        "1".to_string(),
+        main_dis.to_string(),
+        opts.first().unwrap_or(&"").to_string(),
+        other_dis.iter().map(|s| s.to_string()).collect(),
+        opts.iter().skip(1).map(|s| s.to_string()).collect(),
+        Sex::Male,
+        age,
+        3000,
+    );
+    case.recombine();
+    case
+}
+
+// 临时目录中的路径, 以进程号区分同时运行的测试
+#[cfg(any(feature = "batch", feature = "sqlite"))]
+pub fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("rust_drg_{}_{}", std::process::id(), name))
+        .to_string_lossy()
+        .to_string()
+}
+
+// 只有必填列的病例表格的列名与一行病例(A001, J20.900伴E87.102, 分组为EX23)
+#[cfg(any(feature = "batch", feature = "sqlite"))]
+pub fn sample_table() -> (Vec<&'static str>, Vec<&'static str>) {
+    let mut columns = vec!["结算流水号", "主诊断编码", "主手术编码"];
+    columns.extend(OTHER_DIS_COLUMNS);
+    columns.extend(OTHER_OPT_COLUMNS);
+    columns.extend(["性别", "年龄", "体重"]);
+    let mut row = vec!["A001", "J20.900", "", "E87.102"];
+    row.extend(vec![""; 31]);
+    row.extend(["1", "29.0", "2789"]);
+    (columns, row)
+}
+
+// 写出sample_table的CSV文件
+#[cfg(feature = "batch")]
+pub fn write_sample_csv<P: AsRef<Path>>(path: P) {
+    let (columns, row) = sample_table();
+    fs::write(path, format!("{}\n{}\n", columns.join(","), row.join(","))).unwrap();
+}
+
+// 在临时目录中创建只有sample_table一行病例的SQLite数据库, 表名为cases
+#[cfg(feature = "sqlite")]
+pub fn sample_db(name: &str) -> String {
+    let path = temp_path(name);
+    let _ = fs::remove_file(&path);
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let (columns, row) = sample_table();
+    let names: Vec<String> = columns.iter().map(|c| format!("\"{}\"", c)).collect();
+    conn.execute(&format!("CREATE TABLE cases ({})", names.join(", ")), [])
+        .unwrap();
+    // 空字符串写为NULL, 数值列写为数值
+    let values: Vec<rusqlite::types::Value> = row
+        .iter()
+        .map(|v| match v.parse::<f64>() {
+            _ if v.is_empty() => rusqlite::types::Value::Null,
+            Ok(n) => rusqlite::types::Value::Real(n),
+            Err(_) => rusqlite::types::Value::Text(v.to_string()),
+        })
+        .collect();
+    let placeholders = vec!["?"; values.len()].join(", ");
+    conn.execute(
+        &format!("INSERT INTO cases VALUES ({})", placeholders),
+        rusqlite::params_from_iter(values),
+    )
+    .unwrap();
+    path
+}
@@ -0,0 +1,1106 @@
+use crate::audit::{audit_case, AuditFinding};
+use crate::case::{DrgCase, Sex};
+use crate::scheme::{AdrgCondition, CcMccEntry, ComplicationSplit, DrgScheme, MdcaRule, MdcyTable};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::string::String;
+
+// 分组逻辑===========================================================================================
+// MDC判断>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+// 按MDCA先期分组规则判断ADRG(呼吸机使用时间、任一手术等)
+fn is_mdca_rule_adrg(
+    record: &DrgCase,
+    rule: &MdcaRule,
+    adrg_dis_opt: &HashMap<String, HashSet<String>>, // ADRG诊断手术表
+    adrg_name: String,
+) -> String {
+    // 呼吸机使用时间满足时直接入组
+    if let (Some(band), Some(hours)) = (&rule.vent_hours, record.vent_hours) {
+        if band.contains(hours) {
+            return adrg_name;
+        }
+    }
+    if record.no_surgery() {
+        return String::from("KBBZ");
+    }
+    let verb: String = adrg_name.to_string() + "_opt";
+    let opt_list = match adrg_dis_opt.get(&verb) {
+        Some(opt_list) => opt_list,
+        None => return String::from("KBBZ"),
+    };
+    let candidates: Vec<&String> = if rule.any_opt {
+        record.all_opt.iter().collect()
+    } else {
+        vec![&record.main_opt]
+    };
+    for opt in candidates {
+        if !opt_list.contains(opt) {
+            continue;
+        }
+        // 呼吸机相关编码需要呼吸机使用时间也满足(未填写时按编码判断)
+        if rule.vent_opt.contains(opt) {
+            if let (Some(band), Some(hours)) = (&rule.vent_hours, record.vent_hours) {
+                if !band.contains(hours) {
+                    continue;
+                }
+            }
+        }
+        return adrg_name;
+    }
+    return String::from("KBBZ");
+}
+
+// MDCA先期分组
+fn is_mdca(
+    record: &DrgCase,                                // 病例
+    adrg_dis_opt: &HashMap<String, HashSet<String>>, // ADRG诊断手术表
+    all_opt_list: &HashSet<String>,                  // 全部手术列表
+    adrg_type_dict: &HashMap<String, Vec<String>>,   // ADRG类型及对应入组类型
+    adrg_condition: &HashMap<String, AdrgCondition>, // ADRG附加条件
+    mdca_rule: &HashMap<String, MdcaRule>,           // MDCA先期分组规则
+    adrg_list: &[String],                            // MDCA下的ADRG
+) -> String {
+    let mut pred = String::from("KBBZ");
+    for adrg in adrg_list {
+        pred = match mdca_rule.get(adrg) {
+            // 有先期分组规则的ADRG, 仍需满足附加条件
+            Some(rule) => match adrg_condition.get(adrg) {
+                Some(condition) if !condition.is_satisfied(record) => String::from("KBBZ"),
+                _ => is_mdca_rule_adrg(record, rule, adrg_dis_opt, adrg.to_string()),
+            },
+            None => process_adrg(
+                record,
+                adrg_dis_opt,
+                all_opt_list,
+                adrg_type_dict,
+                adrg_condition,
+                adrg.to_string(),
+            ),
+        };
+        if pred != "KBBZ" {
+            break;
+        }
+    }
+    return pred;
+}
+
+// MDCZ中有严重创伤诊断的部位(主诊断或其他诊断), 部位表为MDCZ诊断表中除main_dis外的各表
+pub fn mdcz_trauma_sites(
+    record: &DrgCase,
+    mdcz_adrg_dis_dict: &HashMap<String, HashSet<String>>,
+) -> Vec<String> {
+    let mut sites: Vec<String> = mdcz_adrg_dis_dict
+        .iter()
+        .filter(|(frag, dis)| frag.as_str() != "main_dis" && !dis.is_disjoint(&record.all_dis))
+        .map(|(frag, _)| frag.to_string())
+        .collect();
+    sites.sort();
+    return sites;
+}
+
+// MDCZ多发创伤
+fn is_mdcz(
+    record: &DrgCase,
+    mdcz_adrg_dis_dict: &HashMap<String, HashSet<String>>,
+    mdc_name: String,
+) -> String {
+    // 如果病例没有其他诊断不符合入组条件(需要至少两个诊断)
+    if record.no_other_diagnosis() {
+        return String::from("KBBZ");
+    }
+
+    // 主诊断需为创伤诊断; 方案中没有main_dis表时退而使用各部位表(任一部位的严重创伤诊断均可作为主诊断),
+    // 随附的方案数据没有main_dis表
+    let main_is_trauma = match mdcz_adrg_dis_dict.get("main_dis") {
+        Some(main_dis) => main_dis.contains(&record.main_dis),
+        None => mdcz_adrg_dis_dict
+            .values()
+            .any(|dis| dis.contains(&record.main_dis)),
+    };
+    if !main_is_trauma {
+        return String::from("KBBZ");
+    }
+
+    // 需要主诊断或其他诊断分别在两个不同部位
+    return if mdcz_trauma_sites(record, mdcz_adrg_dis_dict).len() >= 2 {
+        mdc_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// MDCP需要根据的年龄进行判断的
+fn is_age_mdc(
+    record: &DrgCase,
+    mdc_dis: &HashMap<String, HashSet<String>>,
+    mdc_name: String,
+) -> String {
+    // 新生儿: 出生28天以内(含28天)
+    let is_neonate = matches!(record.age_in_days(), Some(days) if days <= 28);
+    if is_neonate && mdc_dis[&mdc_name].contains(&record.main_dis) {
+        return mdc_name;
+    }
+    return String::from("KBBZ");
+}
+
+// MDCY: 主诊断为HIV, 或主诊断为HIV相关疾病且其他诊断中有HIV
+// 方案中没有MDCY诊断表时按主诊断判断
+fn is_hiv_mdc(
+    record: &DrgCase,
+    mdcy_dis: &Option<MdcyTable>,
+    mdc_dis: &HashMap<String, HashSet<String>>,
+    mdc_name: String,
+) -> String {
+    let table = match mdcy_dis {
+        Some(table) => table,
+        None => return is_common_mdc(record, mdc_dis, mdc_name),
+    };
+    if table.hiv_dis.contains(&record.main_dis) || is_hiv_related(record, table) {
+        return mdc_name;
+    }
+    return String::from("KBBZ");
+}
+
+// 主诊断为HIV相关疾病且其他诊断中有HIV
+fn is_hiv_related(record: &DrgCase, table: &MdcyTable) -> bool {
+    table.hiv_related_dis.contains(&record.main_dis)
+        && record.other_dis.iter().any(|d| table.hiv_dis.contains(d))
+}
+
+// MDCM和MDCN需要根据性别进行判断的, 性别未知时主诊断只属于其中一个MDC才能入组
+fn is_sex_mdc(
+    record: &DrgCase,
+    mdc_dis: &HashMap<String, HashSet<String>>,
+    mdc_name: String,
+) -> String {
+    let other_mdc = match mdc_name.as_str() {
+        "MDCM" => "MDCN",
+        _ => "MDCM",
+    };
+    let sex_matched = match record.sex {
+        Sex::Male => mdc_name == "MDCM",
+        Sex::Female => mdc_name == "MDCN",
+        Sex::Unknown => !mdc_dis
+            .get(other_mdc)
+            .is_some_and(|s| s.contains(&record.main_dis)),
+    };
+    if sex_matched && mdc_dis[&mdc_name].contains(&record.main_dis) {
+        return mdc_name;
+    } else {
+        return String::from("KBBZ");
+    }
+}
+
+// 普通的根据主诊断入组的MDC
+fn is_common_mdc(
+    record: &DrgCase,
+    mdc_dis: &HashMap<String, HashSet<String>>,
+    mdc_name: String,
+) -> String {
+    return if mdc_dis[&mdc_name].contains(&record.main_dis) {
+        mdc_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// ADRG判断>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>>
+// 只根据主手术入组的ADRG
+fn is_common_surgery_adrg(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 如果无手术, 则无法入组
+    if record.no_surgery() {
+        return "KBBZ".to_string();
+    }
+    // 主手术在该ADRG的主手术表中
+    let verb: String = adrg_name.to_string() + "_opt"; // 字符串拼接生成键
+    return if adrg_dis_opt_dict[&verb].contains(&record.main_opt) {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 只根据主诊断入组的ADRG
+fn is_common_diagnosis_adrg(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 主诊断在该ADRG的主诊断表中
+    let verb = adrg_name.to_string() + "_dis";
+    return if adrg_dis_opt_dict[&verb].contains(&record.main_dis) {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 特殊入组的ADRG => 主诊断 + 主手术
+fn is_both_mdis_and_mopt_adrg(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 如果病例没有主手术, 无需判断
+    if record.no_surgery() {
+        return String::from("KBBZ");
+    }
+    // 主诊断在该ADRG主诊断表中, 主手术在该ADRG主手术表中
+    let verb_opt: String = adrg_name.to_string() + "_opt";
+    let verb_dis: String = adrg_name.to_string() + "_dis";
+    return if adrg_dis_opt_dict[&verb_dis].contains(&record.main_dis)
+        && adrg_dis_opt_dict[&verb_opt].contains(&record.main_opt)
+    {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 特殊入组ADRG => 主诊断 + 手术(主手术或其他手术) IB1需要主诊断和两个手术
+fn is_both_mdis_opt_adrg(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 如果病例没有主手术不符合入组条件
+    if record.no_surgery() {
+        return String::from("KBBZ");
+    }
+    // 如果病例没有其他手术也不符合入组条件
+    if record.no_other_surgery() {
+        return String::from("KBBZ");
+    }
+
+    let verb_opt1: String = adrg_name.to_string() + "_op1";
+    let verb_opt2: String = adrg_name.to_string() + "_opt2";
+    let verb_dis: String = adrg_name.to_string() + "_dis";
+    return if adrg_dis_opt_dict[&verb_dis].contains(&record.main_dis)
+        && !(adrg_dis_opt_dict[&verb_opt1].is_disjoint(&record.all_opt))
+        && !(adrg_dis_opt_dict[&verb_opt2].is_disjoint(&record.all_opt))
+    {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 特殊入组ADRG => 两个手术
+fn is_both_opt_adrg(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 如果病例没有其他手术, 不符合入组条件
+    if record.no_other_surgery() {
+        return String::from("KBBZ");
+    }
+    let verb_opt1: String = adrg_name.to_string() + "_opt1";
+    let verb_opt2: String = adrg_name.to_string() + "_opt2";
+    return if !(adrg_dis_opt_dict[&verb_opt1].is_disjoint(&record.all_opt))
+        && !(adrg_dis_opt_dict[&verb_opt2].is_disjoint(&record.all_opt))
+    {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 特殊入组的ADRG => 满足主诊断或其他诊断(用于处理PS1\PS2\PS3\PS4)
+fn is_dis_adrg(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 体重等附加条件见分组方案的ADRG条件表
+    let verb_dis: String = adrg_name.to_string() + "_dis";
+    if !(adrg_dis_opt_dict[&verb_dis].is_disjoint(&record.all_dis)) {
+        return adrg_name;
+    }
+    return "KBBZ".to_string();
+}
+
+// 特殊ADRG入组, 主诊断+手术表1+手术表2, 或主诊断+手术表1+手术表3+手术表4
+fn is_mdis_and_multi_surgery_adrg_one(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 无其他手术则不符合入组条件
+    if record.no_other_surgery() {
+        return String::from("KBBZ");
+    }
+
+    let verb_dis = adrg_name.to_string() + "_dis";
+    let verb_opt1 = adrg_name.to_string() + "_opt1";
+    let verb_opt2 = adrg_name.to_string() + "_opt2";
+    let verb_opt3 = adrg_name.to_string() + "_opt3";
+    let verb_opt4 = adrg_name.to_string() + "_opt4";
+
+    return if adrg_dis_opt_dict[&verb_dis].contains(&record.main_dis)
+        && !(adrg_dis_opt_dict[&verb_opt1].is_disjoint(&record.all_opt))
+        && (!(adrg_dis_opt_dict[&verb_opt2].is_disjoint(&record.all_opt))
+            || !(adrg_dis_opt_dict[&verb_opt3].is_disjoint(&record.all_opt))
+                && !(adrg_dis_opt_dict[&verb_opt4].is_disjoint(&record.all_opt)))
+    {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 特殊ADRG入组, 主诊断+手术表1, 或主诊断+手术表2+手术表3
+fn is_mdis_and_multi_surgery_adrg_two(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    let verb_dis: String = adrg_name.to_string() + "_dis";
+    let verb_opt1: String = adrg_name.to_string() + "_opt1";
+    let verb_opt2: String = adrg_name.to_string() + "_opt2";
+    let verb_opt3: String = adrg_name.to_string() + "_opt3";
+
+    return if adrg_dis_opt_dict[&verb_dis].contains(&record.main_dis)
+        && (!(adrg_dis_opt_dict[&verb_opt1].is_disjoint(&record.all_opt))
+            || adrg_dis_opt_dict[&verb_opt2].is_disjoint(&record.all_opt)
+                && adrg_dis_opt_dict[&verb_opt3].is_disjoint(&record.all_opt))
+    {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 特殊入组的ADRG, 包含全部手术
+fn is_all_surgery(record: &DrgCase, all_opt_list: &HashSet<String>, adrg_name: String) -> String {
+    // 如果病例无手术则不符合入组条件
+    if record.no_surgery() {
+        return String::from("KBBZ");
+    }
+
+    // 病例手术在所有手术中满足入组条件
+    return if !(all_opt_list.is_disjoint(&record.all_opt)) {
+        adrg_name
+    } else {
+        String::from("KBBZ")
+    };
+}
+
+// 特殊入组的ADRG, 不包含手术
+fn is_without_surgery(
+    record: &DrgCase,
+    all_opt_list: &HashSet<String>,
+    adrg_name: String,
+) -> String {
+    // 病例无手术则直接满足入组条件
+    if record.no_surgery() {
+        return adrg_name;
+    }
+
+    // 病例的手术都不在所有手术列表中则符合入组条件
+    return if !(all_opt_list.is_disjoint(&record.all_opt)) {
+        String::from("KBBZ")
+    } else {
+        adrg_name
+    };
+}
+
+// 特殊入组的ADRG, 没有WB1手术表中的手术
+fn is_without_wb1_surgery(
+    record: &DrgCase,
+    adrg_dis_opt_dict: &HashMap<String, HashSet<String>>,
+    adrg_name: String,
+) -> String {
+    // 病例无手术则不满足入组条件
+    if record.no_surgery() {
+        return String::from("KBBZ");
+    }
+    if !(adrg_dis_opt_dict["WB1_opt"].is_disjoint(&record.all_opt)) {
+        return adrg_name;
+    }
+    return String::from("KBBZ");
+}
+
+// 综合处理各个ADRG
+fn process_adrg(
+    record: &DrgCase,
+    adrg_dis_opt: &HashMap<String, HashSet<String>>, // ADRG诊断手术表
+    all_opt_list: &HashSet<String>,                  // 全部手术列表
+    adrg_type_dict: &HashMap<String, Vec<String>>,   // ADRG类型及对应入组类型
+    adrg_condition: &HashMap<String, AdrgCondition>, // ADRG附加条件
+    adrg_name: String,
+) -> String {
+    // 不满足附加条件(体重、年龄天数等)的ADRG无法入组
+    if let Some(condition) = adrg_condition.get(&adrg_name) {
+        if !condition.is_satisfied(record) {
+            return String::from("KBBZ");
+        }
+    }
+    let pred_adrg = match adrg_type_dict[&adrg_name][2].as_str() {
+        // 主手术入组
+        "common_opt" => is_common_surgery_adrg(record, adrg_dis_opt, adrg_name),
+        // 主诊断入组
+        "common_dis" => is_common_diagnosis_adrg(record, adrg_dis_opt, adrg_name),
+        // 同时两个手术入组
+        "both_opt" => is_both_opt_adrg(record, adrg_dis_opt, adrg_name),
+        // 主诊断与主手术入组
+        "dis_and_opt" => is_both_mdis_and_mopt_adrg(record, adrg_dis_opt, adrg_name),
+        // 主诊断与手术入组
+        "main_dis_and_any_opt" => is_both_mdis_opt_adrg(record, adrg_dis_opt, adrg_name),
+        // 主诊断与多个手术入组(主诊断+手术表1+手术表2, 主诊断+手术表1+手术表3+手术表4
+        "main_dis_and_multi_opt" => {
+            is_mdis_and_multi_surgery_adrg_one(record, adrg_dis_opt, adrg_name)
+        }
+        // 主诊断与多个手术入组(主诊断+手术表1, 主诊断+手术表2+手术表3
+        "main_dis_and_multi_opt2" => {
+            is_mdis_and_multi_surgery_adrg_two(record, adrg_dis_opt, adrg_name)
+        }
+        // 两个以上诊断入组(PS1|PS2|PS3|PS4)
+        "any_dis" => is_dis_adrg(record, adrg_dis_opt, adrg_name),
+        // 所有手术入组
+        "all_opt" => is_all_surgery(record, all_opt_list, adrg_name),
+        // 无手术入组
+        "no_opt" => is_without_surgery(record, all_opt_list, adrg_name),
+        // 没有WB1手术入组
+        "exclude_wb1_opt" => is_without_wb1_surgery(record, adrg_dis_opt, adrg_name),
+        _ => "KBBZ".to_string(),
+    };
+    return pred_adrg;
+}
+
+// 整体分组===========================================================================================
+fn which_adrg(
+    record: &DrgCase,   // 病例结构
+    scheme: &DrgScheme, // 分组方案
+) -> (String, String) {
+    let mdc_dis = &scheme.mdc_dis; // MDC主诊断表
+    let adrg_dis_opt = &scheme.adrg_dis_opt; // ADRG诊断手术表
+    let mdcz_main_dis_dict = &scheme.mdcz_dis; // MDCZ诊断表
+    let adrg_opt_list = &scheme.all_opt_list; // 所有手术列表
+    let adrg_type_dict = &scheme.adrg_type_dict; // ADRG入组类型
+    let mdc_to_adrg = &scheme.mdc_to_adrg; // MDC下的ADRG
+    let adrg_condition = &scheme.adrg_condition; // ADRG附加条件
+    let mut result_mdc = String::from("KBBZ");
+    let mut result_adrg = String::from("KBBZ");
+    // 顺序为先期分组 -> 新生儿组 -> 艾滋病组 -> 多发创伤组
+    for mdc in [
+        "MDCA", "MDCP", "MDCY", "MDCZ", "MDCB", "MDCC", "MDCD", "MDCE", "MDCF", "MDCG", "MDCH",
+        "MDCI", "MDCJ", "MDCK", "MDCL", "MDCM", "MDCN", "MDCO", "MDCQ", "MDCR", "MDCS", "MDCT",
+        "MDCU", "MDCV", "MDCW", "MDCX",
+    ] {
+        // 先期分组
+        if mdc == "MDCA" {
+            result_adrg = is_mdca(
+                record,
+                adrg_dis_opt,
+                adrg_opt_list,
+                adrg_type_dict,
+                adrg_condition,
+                &scheme.mdca_rule,
+                &mdc_to_adrg[mdc],
+            );
+            if result_adrg != "KBBZ" {
+                result_mdc = "MDCA".to_string();
+                return (result_adrg, result_mdc);
+            }
+        }
+        // 高优先级MDCP
+        else if mdc == "MDCP" {
+            result_mdc = is_age_mdc(record, mdc_dis, mdc.to_string());
+            if result_mdc != "KBBZ" {
+                for adrg in &mdc_to_adrg[mdc] {
+                    result_adrg = process_adrg(
+                        record,
+                        adrg_dis_opt,
+                        adrg_opt_list,
+                        adrg_type_dict,
+                        adrg_condition,
+                        adrg.to_string(),
+                    );
+                    if result_adrg != "KBBZ" {
+                        return (result_adrg, result_mdc);
+                    }
+                }
+            }
+        }
+        // 高优先级MDCY
+        else if mdc == "MDCY" {
+            result_mdc = is_hiv_mdc(record, &scheme.mdcy_dis, mdc_dis, mdc.to_string());
+            if result_mdc != "KBBZ" {
+                for adrg in &mdc_to_adrg[mdc] {
+                    result_adrg = process_adrg(
+                        record,
+                        adrg_dis_opt,
+                        adrg_opt_list,
+                        adrg_type_dict,
+                        adrg_condition,
+                        adrg.to_string(),
+                    );
+                    if result_adrg != "KBBZ" {
+                        return (result_adrg, result_mdc);
+                    }
+                }
+                // 以HIV相关疾病进入的病例, 未进入其他ADRG时进入方案指定的ADRG
+                if let Some(table) = &scheme.mdcy_dis {
+                    if let Some(related_adrg) = &table.related_adrg {
+                        if is_hiv_related(record, table) {
+                            return (related_adrg.to_string(), result_mdc);
+                        }
+                    }
+                }
+            }
+        }
+        // 高优先级MDCZ
+        else if mdc == "MDCZ" {
+            result_mdc = is_mdcz(record, mdcz_main_dis_dict, mdc.to_string());
+            if result_mdc != "KBBZ" {
+                for adrg in &mdc_to_adrg[mdc] {
+                    result_adrg = process_adrg(
+                        record,
+                        adrg_dis_opt,
+                        adrg_opt_list,
+                        adrg_type_dict,
+                        adrg_condition,
+                        adrg.to_string(),
+                    );
+                    if result_adrg != "KBBZ" {
+                        return (result_adrg, result_mdc);
+                    }
+                }
+            }
+        }
+        // 需要考虑性别的MDC大类
+        else if mdc == "MDCM" || mdc == "MDCN" {
+            result_mdc = is_sex_mdc(record, mdc_dis, mdc.to_string());
+            if result_mdc != "KBBZ" {
+                for adrg in &mdc_to_adrg[mdc] {
+                    result_adrg = process_adrg(
+                        record,
+                        adrg_dis_opt,
+                        adrg_opt_list,
+                        adrg_type_dict,
+                        adrg_condition,
+                        adrg.to_string(),
+                    );
+                    if result_adrg != "KBBZ" {
+                        return (result_adrg, result_mdc);
+                    }
+                }
+            }
+        }
+        // 普通凭借主诊断入组的MDC大类
+        else {
+            result_mdc = is_common_mdc(record, mdc_dis, mdc.to_string());
+            if result_mdc != "KBBZ" {
+                for adrg in &mdc_to_adrg[mdc] {
+                    result_adrg = process_adrg(
+                        record,
+                        adrg_dis_opt,
+                        adrg_opt_list,
+                        adrg_type_dict,
+                        adrg_condition,
+                        adrg.to_string(),
+                    );
+                    if result_adrg != "KBBZ" {
+                        return (result_adrg, result_mdc);
+                    }
+                }
+            }
+        }
+    }
+
+    return (result_adrg, result_mdc);
+}
+
+// 导致QY的手术
+#[derive(Debug, Clone, Serialize)]
+pub struct QyOperation {
+    pub code: String,             // 手术编码
+    pub related_mdc: Vec<String>, // 该手术相关的其他MDC, 为空时不属于任何MDC
+}
+
+// 判断是否为QY病例, 返回与所入MDC无关的手术, 为空时不是QY病例
+pub fn qy_operations(
+    record: &DrgCase, // 病例结构
+    adrg_pred: &str,  // 已经入的ADRG组
+    mdc_pred: &str,   // 已经进入的MDC大类
+    scheme: &DrgScheme,
+) -> Vec<QyOperation> {
+    let rule = &scheme.qy_rule;
+    // 未入组、豁免的ADRG(如包含全部手术的ADRG)以及无手术的病例不会出现QY
+    if adrg_pred == "KBBZ" || rule.exempt_adrg.contains(adrg_pred) || record.no_surgery() {
+        return Vec::new();
+    }
+    // 只有初分组为内科ADRG的病例可能为QY, 外科与操作ADRG已经由手术入组
+    let is_medical = scheme
+        .adrg_type_dict
+        .get(adrg_pred)
+        .is_some_and(|t| t[0] == "内科");
+    if !is_medical {
+        return Vec::new();
+    }
+    // 与所入MDC的外科、操作ADRG无关的有效手术(所有手术列表中的手术)导致QY
+    let empty = HashSet::new();
+    let relevant = rule.relevant_opt.get(mdc_pred).unwrap_or(&empty);
+    let mut operations: Vec<QyOperation> = Vec::new();
+    for code in std::iter::once(&record.main_opt).chain(&record.other_opt) {
+        if code.is_empty()
+            || !scheme.all_opt_list.contains(code)
+            || relevant.contains(code)
+            || operations.iter().any(|o| &o.code == code)
+        {
+            continue;
+        }
+        let mut related_mdc: Vec<String> = rule
+            .relevant_opt
+            .iter()
+            .filter(|(mdc, opt)| mdc.as_str() != mdc_pred && opt.contains(code))
+            .map(|(mdc, _)| mdc.clone())
+            .collect();
+        related_mdc.sort();
+        operations.push(QyOperation {
+            code: code.clone(),
+            related_mdc,
+        });
+    }
+    return operations;
+}
+
+// 其他诊断的并发症情况
+#[derive(Debug, Clone, Serialize)]
+pub struct Complication {
+    pub code: String,             // 其他诊断编码
+    pub level: String,            // CC或MCC
+    pub tables: Vec<String>,      // 所属排除表
+    pub excluded_by: Vec<String>, // 同时包含主诊断的排除表
+    pub excluded: bool,           // 是否被主诊断排除
+}
+
+impl Complication {
+    // 排除判断的说明
+    pub fn explain(&self) -> String {
+        if self.tables.is_empty() {
+            format!(
+                "{} {} has no exclusion table, counted",
+                self.code, self.level
+            )
+        } else if self.excluded {
+            format!(
+                "{} {} excluded: principal diagnosis is in {}",
+                self.code,
+                self.level,
+                self.excluded_by.join(", ")
+            )
+        } else {
+            format!(
+                "{} {} counted: principal diagnosis is not in {}",
+                self.code,
+                self.level,
+                self.tables.join(", ")
+            )
+        }
+    }
+}
+
+// 逐一检查其他诊断是否为CC或MCC, 所属的任一排除表包含主诊断时被排除
+pub fn complication_detail(
+    record: &DrgCase,                                // 病例结构
+    exclude_dict: &HashMap<String, HashSet<String>>, // 排除表
+    cc_mcc_dict: &HashMap<String, CcMccEntry>,       // CCMCC表
+) -> Vec<Complication> {
+    let mut detail: Vec<Complication> = Vec::new();
+    for _d in &record.other_dis {
+        let entry = match cc_mcc_dict.get(_d) {
+            Some(entry) if !entry.level.is_empty() => entry,
+            _ => continue,
+        };
+        let excluded_by: Vec<String> = entry
+            .tables
+            .iter()
+            .filter(|t| {
+                exclude_dict
+                    .get(t.as_str())
+                    .is_some_and(|s| s.contains(&record.main_dis))
+            })
+            .cloned()
+            .collect();
+        detail.push(Complication {
+            code: _d.to_string(),
+            level: entry.level.clone(),
+            tables: entry.tables.clone(),
+            excluded: !excluded_by.is_empty(),
+            excluded_by,
+        });
+    }
+    return detail;
+}
+
+// 判断CCMCC
+fn cc_mcc(
+    record: &DrgCase,                                // 病例结构
+    split: ComplicationSplit,                        // 已入ADRG的并发症细分类型
+    exclude_dict: &HashMap<String, HashSet<String>>, // 排除表
+    cc_mcc_dict: &HashMap<String, CcMccEntry>,       // CCMCC表
+) -> String {
+    // 如果该ADRG没有并发症细分, 则并发症类型为9
+    if split == ComplicationSplit::Unsplit {
+        return split.suffix(false, false).to_string();
+    }
+
+    // 有其他诊断的情况下, 逐一检查是否为CC或MCC, 是否被排除
+    let complication_list: Vec<String> = complication_detail(record, exclude_dict, cc_mcc_dict)
+        .into_iter()
+        .filter(|c| !c.excluded)
+        .map(|c| c.level)
+        .collect();
+    let has_mcc = complication_list.iter().any(|l| l == "MCC");
+    let has_cc = complication_list.iter().any(|l| l == "CC");
+    return split.suffix(has_mcc, has_cc).to_string();
+}
+
+// 分组结果
+#[derive(Debug, Clone, Serialize)]
+pub struct DrgResult {
+    pub id: String,                       // 病例ID
+    pub mdc: String,                      // MDC大类
+    pub adrg: String,                     // ADRG组(QY病例为xQY)
+    pub drg: String,                      // DRG细分组
+    pub complications: Vec<Complication>, // 其他诊断的并发症情况
+    pub trauma_sites: Vec<String>,        // 进入MDCZ时计入的严重创伤部位
+    pub qy_operations: Vec<QyOperation>,  // 导致QY的手术
+    pub audit: Vec<AuditFinding>,         // 编码审核问题(含年龄与日期不一致)
+}
+
+impl DrgResult {
+    // 分组明细的说明(创伤部位、QY手术与CC/MCC), 每行一项
+    pub fn explain(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        if !self.trauma_sites.is_empty() {
+            lines.push(format!("Trauma sites: {}", self.trauma_sites.join(", ")));
+        }
+        for o in &self.qy_operations {
+            if o.related_mdc.is_empty() {
+                lines.push(format!("QY operation: {} (not related to any MDC)", o.code));
+            } else {
+                lines.push(format!(
+                    "QY operation: {} (related to {})",
+                    o.code,
+                    o.related_mdc.join(", ")
+                ));
+            }
+        }
+        if self.complications.is_empty() {
+            lines.push("CC/MCC: none".to_string());
+        }
+        lines.extend(self.complications.iter().map(|c| c.explain()));
+        lines
+    }
+}
+
+// 分组路径的结果, 不含并发症明细与编码审核
+#[derive(Debug, Clone, Serialize)]
+pub struct DrgGrouping {
+    pub mdc: String,                     // MDC大类
+    pub adrg: String,                    // ADRG组(QY病例为xQY)
+    pub drg: String,                     // DRG细分组
+    pub qy_operations: Vec<QyOperation>, // 导致QY的手术
+}
+
+// 只进行分组, 不生成并发症明细与编码审核; 供只需要分组结果的批量、方案比较等调用
+pub fn assign_drg(record: &DrgCase, scheme: &DrgScheme) -> DrgGrouping {
+    // 判断进入的MDC
+    let (mut adrg, mdc) = which_adrg(record, scheme);
+
+    // 判断是否为QY
+    let qy_operations = qy_operations(record, &adrg, &mdc, scheme);
+    if !qy_operations.is_empty() {
+        adrg = scheme.qy_rule.qy_code(&mdc);
+    }
+    // 判断CCMCC标志
+    let drg = if adrg != "KBBZ" && qy_operations.is_empty() {
+        let ccmcc_lab = cc_mcc(
+            record,
+            scheme.adrg_split[&adrg],
+            &scheme.exclude_dict,
+            &scheme.cc_mcc_dict,
+        );
+        adrg.to_string() + ccmcc_lab.as_str()
+    } else {
+        adrg.to_string()
+    };
+    DrgGrouping {
+        mdc,
+        adrg,
+        drg,
+        qy_operations,
+    }
+}
+
+// 对单个病例进行分组, 返回包含并发症明细、创伤部位与编码审核的分组结果
+pub fn group_case(record: &DrgCase, scheme: &DrgScheme) -> DrgResult {
+    let grouping = assign_drg(record, scheme);
+    // 进入MDCZ的病例记录计入的创伤部位
+    let trauma_sites = if grouping.mdc == "MDCZ" {
+        mdcz_trauma_sites(record, &scheme.mdcz_dis)
+    } else {
+        Vec::new()
+    };
+    DrgResult {
+        id: record.id.clone(),
+        mdc: grouping.mdc,
+        adrg: grouping.adrg,
+        drg: grouping.drg,
+        complications: complication_detail(record, &scheme.exclude_dict, &scheme.cc_mcc_dict),
+        trauma_sites,
+        qy_operations: grouping.qy_operations,
+        audit: audit_case(record, scheme),
+    }
+}
+
+// 对单个病例进行分组, 只返回DRG编码
+pub fn which_drg(record: &DrgCase, scheme: &DrgScheme) -> String {
+    return assign_drg(record, scheme).drg;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{sample_case, scheme};
+
+    #[test]
+    fn neonate_enters_mdcp() {
+        let mut case = sample_case("A04.402", &[], &[], 0.0);
+        case.age_days = Some(5);
+        assert_eq!(group_case(&case, &scheme()).mdc, "MDCP");
+    }
+
+    #[test]
+    fn adult_age_days_is_ignored() {
+        let scheme = scheme();
+        let adult = sample_case("A04.402", &[], &[], 45.0);
+        let mut with_days = adult.clone();
+        with_days.age_days = Some(0);
+        let result = group_case(&with_days, &scheme);
+        assert_ne!(result.mdc, "MDCP");
+        assert_eq!(result.drg, which_drg(&adult, &scheme));
+    }
+
+    #[test]
+    fn hiv_related_principal_with_hiv_secondary_enters_mdcy() {
+        let scheme = scheme();
+        // 肺结核伴HIV感染
+        let result = group_case(
+            &sample_case("A15.000", &["B20.000x001"], &[], 40.0),
+            &scheme,
+        );
+        assert_eq!(result.mdc, "MDCY");
+        assert_eq!(result.adrg, "YR1");
+        // 没有HIV其他诊断时不进入MDCY
+        assert_ne!(
+            group_case(&sample_case("A15.000", &[], &[], 40.0), &scheme).mdc,
+            "MDCY"
+        );
+        // HIV暴露不是HIV诊断
+        let exposure = sample_case("A15.000", &["Z20.600"], &[], 40.0);
+        assert_ne!(group_case(&exposure, &scheme).mdc, "MDCY");
+    }
+
+    #[test]
+    fn hiv_principal_enters_mdcy() {
+        let result = group_case(&sample_case("B20.000x001", &[], &[], 40.0), &scheme());
+        assert_eq!(result.mdc, "MDCY");
+    }
+
+    #[test]
+    fn transplant_as_secondary_operation_is_pre_grouped() {
+        // 肾移植作为其他手术
+        let case = sample_case("N18.500", &[], &["39.9500", "55.6100"], 50.0);
+        assert_eq!(group_case(&case, &scheme()).adrg, "AE1");
+    }
+
+    #[test]
+    fn ecmo_and_ventilation_enter_ah1() {
+        let scheme = scheme();
+        let ecmo = sample_case("J96.000", &[], &["31.1x00", "39.6500"], 50.0);
+        assert_eq!(group_case(&ecmo, &scheme).adrg, "AH1");
+        // 呼吸机使用时间不足96小时时呼吸机编码不进入AH1
+        let mut vent = sample_case("J96.000", &[], &["96.7201"], 50.0);
+        vent.vent_hours = Some(50.0);
+        assert_ne!(group_case(&vent, &scheme).adrg, "AH1");
+        vent.vent_hours = Some(120.0);
+        assert_eq!(group_case(&vent, &scheme).adrg, "AH1");
+        // 没有手术但呼吸机使用时间满足
+        let mut long_vent = sample_case("J96.000", &[], &[], 50.0);
+        long_vent.vent_hours = Some(100.0);
+        assert_eq!(group_case(&long_vent, &scheme).adrg, "AH1");
+    }
+
+    #[test]
+    fn complication_exclusion_matches_single_table_data() {
+        // E87.102属于表7-52, E70.000在表7-52中, J20.900不在
+        let scheme = scheme();
+        let excluded = sample_case("E70.000", &["E87.102"], &[], 30.0);
+        let detail = complication_detail(&excluded, &scheme.exclude_dict, &scheme.cc_mcc_dict);
+        assert_eq!(detail.len(), 1);
+        assert!(detail[0].excluded);
+        assert_eq!(detail[0].excluded_by, vec!["表7-52".to_string()]);
+
+        let counted = sample_case("J20.900", &["E87.102", "I10.x00"], &[], 30.0);
+        let detail = complication_detail(&counted, &scheme.exclude_dict, &scheme.cc_mcc_dict);
+        assert_eq!(detail.len(), 1);
+        assert!(!detail[0].excluded);
+        assert_eq!(group_case(&counted, &scheme).drg, "EX23");
+    }
+
+    #[test]
+    fn complication_excluded_by_any_of_its_tables() {
+        let exclude_dict: HashMap<String, HashSet<String>> = HashMap::from([
+            ("T1".to_string(), HashSet::from(["A00.000".to_string()])),
+            (
+                "T2".to_string(),
+                HashSet::from(["B00.000".to_string(), "A00.000".to_string()]),
+            ),
+        ]);
+        let entry = |level: &str, tables: &[&str]| CcMccEntry {
+            level: level.to_string(),
+            tables: tables.iter().map(|t| t.to_string()).collect(),
+        };
+        let cc_mcc_dict: HashMap<String, CcMccEntry> = HashMap::from([
+            ("X00.000".to_string(), entry("CC", &["T1", "T2"])),
+            ("Y00.000".to_string(), entry("MCC", &[])),
+            ("Z00.000".to_string(), entry("", &["T1"])),
+        ]);
+        let other = ["X00.000", "Y00.000", "Z00.000"];
+
+        let case = sample_case("B00.000", &other, &[], 30.0);
+        let detail = complication_detail(&case, &exclude_dict, &cc_mcc_dict);
+        assert_eq!(detail.len(), 2);
+        assert_eq!(detail[0].excluded_by, vec!["T2".to_string()]);
+        assert!(!detail[1].excluded);
+
+        let case = sample_case("A00.000", &other, &[], 30.0);
+        let detail = complication_detail(&case, &exclude_dict, &cc_mcc_dict);
+        assert_eq!(
+            detail[0].excluded_by,
+            vec!["T1".to_string(), "T2".to_string()]
+        );
+        assert_eq!(
+            cc_mcc(&case, ComplicationSplit::Full, &exclude_dict, &cc_mcc_dict),
+            "1"
+        );
+
+        let case = sample_case("C00.000", &["X00.000"], &[], 30.0);
+        assert!(!complication_detail(&case, &exclude_dict, &cc_mcc_dict)[0].excluded);
+        assert_eq!(
+            cc_mcc(&case, ComplicationSplit::Full, &exclude_dict, &cc_mcc_dict),
+            "3"
+        );
+    }
+
+    #[test]
+    fn unsplit_adrg_ignores_complications() {
+        // US1为无细分ADRG, 有CC时仍为US19
+        let case = sample_case("F11.000", &["E87.102"], &[], 30.0);
+        assert_eq!(group_case(&case, &scheme()).drg, "US19");
+    }
+
+    #[test]
+    fn unrelated_operation_makes_medical_case_qy() {
+        // 39.9014属于MDCF的外科ADRG, 与MDCE无关
+        let scheme = scheme();
+        let result = group_case(&sample_case("J20.900", &[], &["39.9014"], 30.0), &scheme);
+        assert_eq!((result.adrg.as_str(), result.drg.as_str()), ("EQY", "EQY"));
+        assert_eq!(result.qy_operations.len(), 1);
+        assert_eq!(result.qy_operations[0].code, "39.9014");
+        assert_eq!(
+            result.qy_operations[0].related_mdc,
+            vec!["MDCF".to_string()]
+        );
+
+        // 不在手术列表中的编码不导致QY
+        let result = group_case(&sample_case("J20.900", &[], &["99.9999"], 30.0), &scheme);
+        assert_eq!(result.drg, "EX25");
+        assert!(result.qy_operations.is_empty());
+    }
+
+    #[test]
+    fn mdcd_qy_code_is_dqy() {
+        // 原实现将MDCD的QY写作SQY, 与MDCS重复
+        let case = sample_case("H66.900", &[], &["39.9014"], 30.0);
+        assert_eq!(group_case(&case, &scheme()).drg, "DQY");
+    }
+
+    #[test]
+    fn grouping_only_matches_full_result() {
+        let scheme = scheme();
+        for case in [
+            sample_case("J20.900", &["E87.102"], &[], 30.0),
+            sample_case("J20.900", &[], &["39.9014"], 30.0),
+            sample_case("N18.500", &[], &["39.9500", "55.6100"], 50.0),
+        ] {
+            let full = group_case(&case, &scheme);
+            let grouping = assign_drg(&case, &scheme);
+            assert_eq!(
+                (grouping.mdc, grouping.adrg, grouping.drg.clone()),
+                (full.mdc, full.adrg, full.drg)
+            );
+            assert_eq!(which_drg(&case, &scheme), grouping.drg);
+        }
+    }
+
+    #[test]
+    fn mdcz_requires_trauma_principal_and_two_sites() {
+        let scheme = scheme();
+        // 头部与胸部两个部位
+        let result = group_case(&sample_case("S01.801", &["S21.101"], &[], 40.0), &scheme);
+        assert_eq!(result.mdc, "MDCZ");
+        assert_eq!(result.trauma_sites, vec!["chest_dis", "head_dis"]);
+        assert!(result
+            .explain()
+            .contains(&"Trauma sites: chest_dis, head_dis".to_string()));
+
+        // 只有头部一个部位
+        let result = group_case(
+            &sample_case("S01.801", &["S01.800x011"], &[], 40.0),
+            &scheme,
+        );
+        assert_ne!(result.mdc, "MDCZ");
+        assert!(result.trauma_sites.is_empty());
+
+        // 主诊断不是创伤诊断
+        let case = sample_case("J20.900", &["S01.801", "S21.101"], &[], 40.0);
+        assert_ne!(group_case(&case, &scheme).mdc, "MDCZ");
+        assert_eq!(is_mdcz(&case, &scheme.mdcz_dis, "MDCZ".to_string()), "KBBZ");
+    }
+
+    #[test]
+    fn mdcz_main_dis_table_restricts_principal() {
+        let mut tables: HashMap<String, HashSet<String>> = HashMap::from([
+            (
+                "head_dis".to_string(),
+                HashSet::from(["S01.801".to_string()]),
+            ),
+            (
+                "chest_dis".to_string(),
+                HashSet::from(["S21.101".to_string()]),
+            ),
+        ]);
+        let head_first = sample_case("S01.801", &["S21.101"], &[], 40.0);
+        let chest_first = sample_case("S21.101", &["S01.801"], &[], 40.0);
+        // 没有main_dis表时任一部位表中的编码都可以作为主诊断
+        assert_eq!(is_mdcz(&head_first, &tables, "MDCZ".to_string()), "MDCZ");
+        assert_eq!(is_mdcz(&chest_first, &tables, "MDCZ".to_string()), "MDCZ");
+
+        tables.insert(
+            "main_dis".to_string(),
+            HashSet::from(["S01.801".to_string()]),
+        );
+        assert_eq!(is_mdcz(&head_first, &tables, "MDCZ".to_string()), "MDCZ");
+        assert_eq!(is_mdcz(&chest_first, &tables, "MDCZ".to_string()), "KBBZ");
+    }
+}
@@ -0,0 +1,129 @@
+use crate::case::DrgCase;
+use crate::group::which_drg;
+use crate::scheme::{content_hash, DrgScheme};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::string::String;
+
+// 保存的单个病例分组结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredResult {
+    pub hash: String, // 病例内容哈希
+    pub drg: String,  // 分组结果
+}
+
+// 分组结果发生变化的病例
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupingDelta {
+    pub id: String,              // 结算流水号
+    pub old_drg: Option<String>, // 之前的分组结果, 新病例为None
+    pub new_drg: String,         // 本次分组结果
+}
+
+// 增量分组的结果
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalResult {
+    pub drg: Vec<String>,          // 与输入病例顺序一致的分组结果
+    pub regrouped: usize,          // 重新分组的病例数
+    pub delta: Vec<GroupingDelta>, // 分组结果发生变化的病例
+}
+
+// 增量分组存储, 键为结算流水号, 分组方案指纹变化时全部重新分组
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RegroupStore {
+    pub scheme: String,                         // 分组方案指纹
+    pub records: HashMap<String, StoredResult>, // 各病例的内容哈希与分组结果
+}
+
+// 病例内容哈希, 由病例的JSON形式计算
+pub fn case_hash(record: &DrgCase) -> String {
+    let json = serde_json::to_vec(record).unwrap_or_default();
+    content_hash([json.as_slice()])
+}
+
+impl RegroupStore {
+    // 读取存储文件, 文件不存在时返回空存储
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    // 写入存储文件
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    // 只对新增或内容变化的病例重新分组, 其余沿用存储的结果; 本次未出现的病例保留在存储中
+    pub fn regroup(&mut self, cases: &[DrgCase], scheme: &DrgScheme) -> IncrementalResult {
+        let scheme_changed = self.scheme != scheme.fingerprint;
+        self.scheme = scheme.fingerprint.clone();
+        let mut result = IncrementalResult::default();
+        for case in cases {
+            let hash = case_hash(case);
+            let stored = self.records.get(&case.id);
+            if let Some(s) = stored {
+                if !scheme_changed && s.hash == hash {
+                    result.drg.push(s.drg.clone());
+                    continue;
+                }
+            }
+            let old_drg = stored.map(|s| s.drg.clone());
+            let drg = which_drg(case, scheme);
+            result.regrouped += 1;
+            if old_drg.as_ref() != Some(&drg) {
+                result.delta.push(GroupingDelta {
+                    id: case.id.clone(),
+                    old_drg,
+                    new_drg: drg.clone(),
+                });
+            }
+            self.records.insert(
+                case.id.clone(),
+                StoredResult {
+                    hash,
+                    drg: drg.clone(),
+                },
+            );
+            result.drg.push(drg);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{sample_case, scheme};
+
+    fn case_with_id(id: &str, other_dis: &str) -> DrgCase {
+        let mut case = sample_case("J20.900", &[other_dis], &[], 29.0);
+        case.id = id.to_string();
+        case
+    }
+
+    #[test]
+    fn regroups_only_changed_cases() {
+        let scheme = scheme();
+        let mut store = RegroupStore::default();
+        let first = store.regroup(&[case_with_id("A001", "E87.102")], &scheme);
+        assert_eq!(first.regrouped, 1);
+        assert_eq!(first.delta[0].id, "A001");
+        assert_eq!(first.delta[0].old_drg, None);
+        assert!(store.records.contains_key("A001"));
+
+        let same = store.regroup(&[case_with_id("A001", "E87.102")], &scheme);
+        assert_eq!(same.regrouped, 0);
+        assert!(same.delta.is_empty());
+        assert_eq!(same.drg, first.drg);
+
+        let changed = store.regroup(&[case_with_id("A001", "I10.x00")], &scheme);
+        assert_eq!(changed.regrouped, 1);
+    }
+}
@@ -0,0 +1,31 @@
+#![allow(clippy::needless_return, clippy::too_many_arguments)]
+
+pub mod advisor; // 主诊断/主手术选择建议
+pub mod audit; // 编码质量审核
+#[cfg(feature = "batch")]
+pub mod batch; // 表格批量分组
+pub mod case; // 病例结构
+pub mod group; // 分组逻辑
+pub mod incremental; // 增量分组
+pub mod migration; // 分组方案迁移影响
+pub mod pipe; // 管道模式
+pub mod scheme; // 分组方案
+pub mod scheme_diff; // 分组方案差异
+pub mod sensitivity; // 高编敏感性分析
+#[cfg(feature = "sqlite")]
+pub mod sqlite; // SQLite读写
+#[cfg(feature = "batch")]
+pub mod watch; // 监视目录批量分组
+pub mod whatif; // 修改编码的模拟分组
+
+#[cfg(test)]
+mod fixtures; // 测试共用的方案、病例与输入文件
+
+#[cfg(feature = "python")]
+pub mod python; // Python绑定
+
+#[cfg(feature = "wasm")]
+pub mod wasm; // WebAssembly绑定
+
+#[cfg(feature = "capi")]
+pub mod capi; // C接口
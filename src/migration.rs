@@ -0,0 +1,187 @@
+use crate::case::DrgCase;
+use crate::group::which_drg;
+use crate::scheme::DrgScheme;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::string::String;
+
+// 同一病例在新旧两套分组方案下的分组结果
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationCase {
+    pub id: String,                    // 病例ID
+    pub hospital_code: Option<String>, // 定点医疗机构代码
+    pub old_drg: String,               // 旧方案分组
+    pub new_drg: String,               // 新方案分组
+    pub old_weight: Option<f64>,       // 旧方案权重
+    pub new_weight: Option<f64>,       // 新方案权重
+}
+
+impl MigrationCase {
+    // 旧方案可以入组而新方案无法入组(KBBZ); 进入xQY视为已入组
+    pub fn became_ungroupable(&self) -> bool {
+        self.old_drg != "KBBZ" && self.new_drg == "KBBZ"
+    }
+}
+
+// 用新旧两套分组方案对同一批病例分组
+pub fn compare_schemes(
+    cases: &[DrgCase],
+    old_scheme: &DrgScheme,
+    new_scheme: &DrgScheme,
+) -> Vec<MigrationCase> {
+    cases
+        .iter()
+        .map(|case| {
+            let old_drg = which_drg(case, old_scheme);
+            let new_drg = which_drg(case, new_scheme);
+            MigrationCase {
+                id: case.id.clone(),
+                hospital_code: case.hospital_code.clone(),
+                old_weight: old_scheme.drg_weight.get(&old_drg).copied(),
+                new_weight: new_scheme.drg_weight.get(&new_drg).copied(),
+                old_drg,
+                new_drg,
+            }
+        })
+        .collect()
+}
+
+// 旧DRG到新DRG的转移矩阵, 按(旧DRG, 新DRG)排序
+pub fn transition_matrix(cases: &[MigrationCase]) -> Vec<(String, String, usize)> {
+    let mut matrix: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for case in cases {
+        *matrix
+            .entry((case.old_drg.clone(), case.new_drg.clone()))
+            .or_default() += 1;
+    }
+    matrix
+        .into_iter()
+        .map(|((old, new), count)| (old, new, count))
+        .collect()
+}
+
+// 按医疗机构汇总的迁移影响
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HospitalImpact {
+    pub hospital_code: String, // 定点医疗机构代码, 未填写时为空字符串
+    pub cases: usize,          // 病例数
+    pub changed: usize,        // 分组发生变化的病例数
+    pub ungroupable: usize,    // 旧方案可以入组而新方案无法入组(KBBZ)的病例数, xQY不计入
+    pub old_weight: f64,       // 旧方案总权重(权重表中没有的DRG不计)
+    pub new_weight: f64,       // 新方案总权重(权重表中没有的DRG不计)
+}
+
+// 按医疗机构汇总病例数与权重变化
+pub fn hospital_impact(cases: &[MigrationCase]) -> Vec<HospitalImpact> {
+    let mut groups: BTreeMap<String, HospitalImpact> = BTreeMap::new();
+    for case in cases {
+        let code = case.hospital_code.clone().unwrap_or_default();
+        let impact = groups.entry(code.clone()).or_insert(HospitalImpact {
+            hospital_code: code,
+            ..Default::default()
+        });
+        impact.cases += 1;
+        if case.old_drg != case.new_drg {
+            impact.changed += 1;
+        }
+        if case.became_ungroupable() {
+            impact.ungroupable += 1;
+        }
+        impact.old_weight += case.old_weight.unwrap_or(0.0);
+        impact.new_weight += case.new_weight.unwrap_or(0.0);
+    }
+    groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{sample_case, scheme};
+    use std::collections::HashMap;
+
+    fn hospital_case(id: &str, hospital: &str, other_dis: &[&str]) -> DrgCase {
+        let mut case = sample_case("J20.900", other_dis, &[], 30.0);
+        case.id = id.to_string();
+        case.hospital_code = Some(hospital.to_string());
+        case
+    }
+
+    fn migration_case(hospital: Option<&str>, old_drg: &str, new_drg: &str) -> MigrationCase {
+        MigrationCase {
+            id: "1".to_string(),
+            hospital_code: hospital.map(|h| h.to_string()),
+            old_drg: old_drg.to_string(),
+            new_drg: new_drg.to_string(),
+            old_weight: Some(1.0),
+            new_weight: (new_drg != "KBBZ").then_some(2.0),
+        }
+    }
+
+    #[test]
+    fn compares_cases_under_both_schemes() {
+        let mut old_scheme = scheme();
+        old_scheme.drg_weight =
+            HashMap::from([("EX25".to_string(), 0.5), ("EX23".to_string(), 0.8)]);
+        let mut new_scheme = scheme();
+        // 新方案中J20.900不再属于MDCE
+        new_scheme
+            .mdc_dis
+            .get_mut("MDCE")
+            .unwrap()
+            .remove("J20.900");
+        let cases = [
+            hospital_case("1", "H1", &[]),
+            hospital_case("2", "H1", &["E87.102"]),
+        ];
+        let result = compare_schemes(&cases, &old_scheme, &new_scheme);
+        assert_eq!(result.len(), 2);
+        assert_eq!(
+            (result[0].old_drg.as_str(), result[1].old_drg.as_str()),
+            ("EX25", "EX23")
+        );
+        assert!(result.iter().all(|c| c.became_ungroupable()));
+        assert_eq!(result[1].old_weight, Some(0.8));
+        assert_eq!(result[1].new_weight, None);
+        assert_eq!(result[0].hospital_code.as_deref(), Some("H1"));
+    }
+
+    #[test]
+    fn counts_transitions() {
+        let cases = [
+            migration_case(None, "EX25", "EX25"),
+            migration_case(None, "EX25", "EX23"),
+            migration_case(None, "EX25", "EX23"),
+            migration_case(None, "KBBZ", "EX23"),
+        ];
+        assert_eq!(
+            transition_matrix(&cases),
+            vec![
+                ("EX25".to_string(), "EX23".to_string(), 2),
+                ("EX25".to_string(), "EX25".to_string(), 1),
+                ("KBBZ".to_string(), "EX23".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn summarizes_impact_by_hospital() {
+        let cases = [
+            migration_case(Some("H1"), "EX25", "EX25"),
+            migration_case(Some("H1"), "EX25", "KBBZ"),
+            migration_case(Some("H1"), "EX25", "EQY"),
+            migration_case(Some("H1"), "KBBZ", "KBBZ"),
+            migration_case(None, "EX23", "KBBZ"),
+        ];
+        let impact = hospital_impact(&cases);
+        assert_eq!(impact.len(), 2);
+        // 未填写医疗机构的病例排在最前
+        assert_eq!(impact[0].hospital_code, "");
+        assert_eq!((impact[0].cases, impact[0].ungroupable), (1, 1));
+        let h1 = &impact[1];
+        assert_eq!(h1.hospital_code, "H1");
+        assert_eq!((h1.cases, h1.changed), (4, 2));
+        // 进入EQY以及原本就无法入组的病例不计入
+        assert_eq!(h1.ungroupable, 1);
+        assert_eq!((h1.old_weight, h1.new_weight), (4.0, 4.0));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::case::DrgCase;
+use crate::group::group_case;
+use crate::scheme::DrgScheme;
+use serde_json::json;
+use std::io::{self, BufRead, Write};
+
+// 逐行读取JSON病例并逐行写出JSON分组结果, 空行跳过;
+// 无法解析的行写出{"line": 行号, "error": 错误信息}, 不中断后续病例
+pub fn drg_group_lines<R: BufRead, W: Write>(
+    input: R,
+    output: &mut W,
+    scheme: &DrgScheme, // 分组方案
+) -> io::Result<()> {
+    for (idx, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = match DrgCase::from_json(&line) {
+            Ok(drg_case) => serde_json::to_string(&group_case(&drg_case, scheme))?,
+            Err(e) => json!({"line": idx + 1, "error": e.to_string()}).to_string(),
+        };
+        writeln!(output, "{}", result)?;
+        // 每行立即写出, 调用方可以逐行读取结果
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::scheme;
+    use serde_json::Value;
+
+    fn run(input: &str) -> Vec<Value> {
+        let mut output: Vec<u8> = Vec::new();
+        drg_group_lines(input.as_bytes(), &mut output, &scheme()).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn groups_each_line_as_a_result_object() {
+        let results = run(
+            r#"{"id": "1", "main_dis": "J20.900", "other_dis": ["E87.102"], "sex": 1, "age": 29.0, "weight": 2789}"#,
+        );
+        assert_eq!(results.len(), 1);
+        let result = results[0].as_object().unwrap();
+        let keys: Vec<&str> = result.keys().map(|k| k.as_str()).collect();
+        for key in [
+            "id",
+            "mdc",
+            "adrg",
+            "drg",
+            "complications",
+            "trauma_sites",
+            "qy_operations",
+            "audit",
+        ] {
+            assert!(keys.contains(&key), "{} not in {:?}", key, keys);
+        }
+        assert_eq!(result["id"], "1");
+        assert_eq!(result["drg"], "EX23");
+        assert_eq!(result["complications"][0]["code"], "E87.102");
+    }
+
+    #[test]
+    fn malformed_line_yields_an_error_record_and_continues() {
+        let results = run(concat!(
+            "not json\n",
+            "\n",
+            r#"{"id": "2", "main_dis": "J20.900", "sex": 1, "age": 29.0, "weight": 2789}"#,
+            "\n"
+        ));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["line"], 1);
+        assert!(results[0]["error"].is_string());
+        assert_eq!(results[1]["id"], "2");
+        assert_eq!(results[1]["drg"], "EX25");
+    }
+}
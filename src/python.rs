@@ -0,0 +1,163 @@
+use crate::case::{CaseRow, DrgCase, RowError};
+use crate::group::{group_case, DrgResult};
+use crate::scheme::DrgScheme;
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyFloat, PyList};
+use serde_json::Value;
+use std::string::String;
+
+// Python字典形式的一行病例, 键与批量分组表格的列名一致
+struct DictRow<'a, 'py>(&'a Bound<'py, PyDict>);
+
+impl DictRow<'_, '_> {
+    // 读取字典中的值, 缺失/None/NaN返回None
+    fn value(&self, key: &str) -> Result<Option<Bound<'_, PyAny>>, RowError> {
+        let value = match self.0.get_item(key) {
+            Ok(Some(v)) if !v.is_none() => v,
+            Ok(_) => return Ok(None),
+            Err(e) => return Err(RowError::Invalid(e.to_string())),
+        };
+        if let Ok(f) = value.downcast::<PyFloat>() {
+            if f.value().is_nan() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(value))
+    }
+}
+
+impl CaseRow for DictRow<'_, '_> {
+    fn text(&self, key: &str) -> Result<Option<String>, RowError> {
+        let value = match self.value(key)? {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let text = value
+            .str()
+            .map_err(|e| RowError::Invalid(e.to_string()))?
+            .to_string();
+        let text = text.trim();
+        Ok(if text.is_empty() {
+            None
+        } else {
+            Some(text.to_string())
+        })
+    }
+
+    fn number(&self, key: &str) -> Result<Option<f64>, RowError> {
+        match self.value(key)? {
+            Some(v) => v
+                .extract::<f64>()
+                .map(Some)
+                .map_err(|_| RowError::Invalid(format!("{} must be a number", key))),
+            None => Ok(None),
+        }
+    }
+}
+
+// 从字典构造病例结构, 字段规则与批量分组相同(见DrgCase::from_row)
+fn case_from_dict(row: &Bound<'_, PyDict>) -> PyResult<DrgCase> {
+    DrgCase::from_row(&DictRow(row)).map_err(|e| match e {
+        RowError::Missing(key) => PyKeyError::new_err(key),
+        RowError::Invalid(message) => PyValueError::new_err(message),
+    })
+}
+
+// 将JSON值转换为Python对象
+fn json_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_pyobject(py)?.to_owned().into_any().unbind(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into_pyobject(py)?.into_any().unbind(),
+            None => n.as_f64().into_pyobject(py)?.into_any().unbind(),
+        },
+        Value::String(s) => s.into_pyobject(py)?.into_any().unbind(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(json_to_py(py, item)?)?;
+            }
+            list.into_any().unbind()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (k, v) in map {
+                dict.set_item(k, json_to_py(py, v)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
+}
+
+// 将分组结果转换为Python字典, 字段与管道模式输出的JSON一致
+fn result_to_dict<'py>(py: Python<'py>, result: &DrgResult) -> PyResult<Bound<'py, PyDict>> {
+    let value = serde_json::to_value(result).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(json_to_py(py, &value)?
+        .into_bound(py)
+        .downcast_into::<PyDict>()?)
+}
+
+// 分组器, 持有一套分组方案
+#[pyclass]
+pub struct Grouper {
+    scheme: DrgScheme,
+}
+
+#[pymethods]
+impl Grouper {
+    // 从分组方案目录初始化分组器
+    #[new]
+    fn new(scheme_dir: &str) -> PyResult<Self> {
+        let scheme = DrgScheme::from_dir(scheme_dir)
+            .map_err(|e| PyValueError::new_err(format!("failed to load scheme: {}", e)))?;
+        Ok(Self { scheme })
+    }
+
+    // 对单个病例(字典)进行分组
+    fn group<'py>(
+        &self,
+        py: Python<'py>,
+        case: &Bound<'py, PyDict>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let record = case_from_dict(case)?;
+        result_to_dict(py, &group_case(&record, &self.scheme))
+    }
+
+    // 对多个病例(字典列表)进行分组
+    fn group_records<'py>(
+        &self,
+        py: Python<'py>,
+        cases: &Bound<'py, PyList>,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut results = Vec::new();
+        for case in cases.iter() {
+            let row = case.downcast::<PyDict>()?;
+            results.push(self.group(py, row)?);
+        }
+        Ok(results)
+    }
+
+    // 对pandas或polars的DataFrame进行分组, 返回字典列表
+    fn group_frame<'py>(
+        &self,
+        py: Python<'py>,
+        frame: &Bound<'py, PyAny>,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let records = if frame.hasattr("to_dicts")? {
+            // polars.DataFrame
+            frame.call_method0("to_dicts")?
+        } else {
+            // pandas.DataFrame
+            frame.call_method1("to_dict", ("records",))?
+        };
+        self.group_records(py, records.downcast::<PyList>()?)
+    }
+}
+
+#[pymodule]
+fn rust_drg(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Grouper>()?;
+    Ok(())
+}
@@ -0,0 +1,622 @@
+use crate::audit::AuditRule;
+use crate::case::DrgCase;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::string::String;
+
+// 分组方案目录中的文件
+pub const SCHEME_FILES: [&str; 8] = [
+    "MDC_main_dis.json",
+    "MDCZ_main_dis_list.json",
+    "adrg_dis_opt.json",
+    "adrg_type_dict.json",
+    "mdc_map_adrg.json",
+    "cc_mcc_dict.json",
+    "exclusive_dict.json",
+    "all_opt_sheet.txt",
+];
+
+// 分组方案目录中的可选文件, 缺失时视为空表
+pub const OPTIONAL_SCHEME_FILES: [&str; 6] = [
+    "adrg_condition.json",
+    "MDCY_main_dis_list.json",
+    "mdca_rule.json",
+    "qy_rule.json",
+    "drg_weight.json",
+    "audit_rule.json",
+];
+
+// 读取分组方案=======================================================================================
+// 取出分组方案中某个文件的内容
+fn scheme_file<'a>(
+    files: &'a HashMap<String, Vec<u8>>,
+    name: &str,
+) -> Result<&'a [u8], Box<dyn Error>> {
+    match files.get(name) {
+        Some(bytes) => Ok(bytes.as_slice()),
+        None => Err(format!("scheme file {} is missing", name).into()),
+    }
+}
+
+// 解析JSON文件内容为字典(HashMap)
+fn parse_json_table<T: DeserializeOwned>(
+    files: &HashMap<String, Vec<u8>>,
+    name: &str,
+) -> Result<T, Box<dyn Error>> {
+    serde_json::from_slice(scheme_file(files, name)?).map_err(|e| format!("{}: {}", name, e).into())
+}
+
+// 解析可选的JSON文件, 缺失时返回空表
+fn parse_optional_json_table<T: DeserializeOwned + Default>(
+    files: &HashMap<String, Vec<u8>>,
+    name: &str,
+) -> Result<T, Box<dyn Error>> {
+    if files.contains_key(name) {
+        parse_json_table(files, name)
+    } else {
+        Ok(T::default())
+    }
+}
+
+// 计算内容哈希(64位FNV-1a), 结果与平台和版本无关, 可以持久化保存
+pub fn content_hash<'a, I: IntoIterator<Item = &'a [u8]>>(parts: I) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        // 以长度分隔各部分, 避免拼接后内容相同
+        for byte in (part.len() as u64).to_le_bytes().iter().chain(part) {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+// 解析所有手术列表(以逗号分隔)
+fn parse_icd9_list(bytes: &[u8]) -> Result<HashSet<String>, Box<dyn Error>> {
+    let contents = std::str::from_utf8(bytes)?;
+    let vec: HashSet<String> = contents.split(',').map(|s| s.to_string()).collect();
+    Ok(vec)
+}
+
+// 按文件名顺序计算分组方案指纹
+fn scheme_fingerprint(files: &HashMap<String, Vec<u8>>) -> String {
+    let mut names: Vec<&String> = files.keys().collect();
+    names.sort();
+    content_hash(
+        names
+            .into_iter()
+            .flat_map(|name| [name.as_bytes(), files[name].as_slice()]),
+    )
+}
+
+// ADRG附加条件=====================================================================================
+fn default_true() -> bool {
+    true
+}
+
+// 数值区间, 下限默认包含, 上限默认不包含, 缺省的一端不限制
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValueBand {
+    pub min: Option<f64>,
+    #[serde(default = "default_true")]
+    pub min_inclusive: bool,
+    pub max: Option<f64>,
+    #[serde(default)]
+    pub max_inclusive: bool,
+}
+
+impl ValueBand {
+    // 数值是否落在区间内
+    pub fn contains(&self, value: f64) -> bool {
+        let above_min = match self.min {
+            Some(min) if self.min_inclusive => value >= min,
+            Some(min) => value > min,
+            None => true,
+        };
+        let below_max = match self.max {
+            Some(max) if self.max_inclusive => value <= max,
+            Some(max) => value < max,
+            None => true,
+        };
+        above_min && below_max
+    }
+}
+
+// ADRG在入组类型之外需要满足的附加条件(adrg_condition.json), 键为ADRG编码
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdrgCondition {
+    pub weight: Option<ValueBand>, // 新生儿体重(克), 优先出生体重, 其次入院体重
+    pub age_days: Option<ValueBand>, // 年龄天数, 满一岁的病例不满足
+    pub vent_hours: Option<ValueBand>, // 有创呼吸机使用时间(小时), 未填写的病例不满足
+}
+
+impl AdrgCondition {
+    // 病例是否满足全部附加条件
+    pub fn is_satisfied(&self, record: &DrgCase) -> bool {
+        if let Some(band) = &self.weight {
+            if !band.contains(record.neonatal_weight() as f64) {
+                return false;
+            }
+        }
+        if let Some(band) = &self.age_days {
+            match record.age_in_days() {
+                Some(days) if band.contains(days as f64) => (),
+                _ => return false,
+            }
+        }
+        if let Some(band) = &self.vent_hours {
+            match record.vent_hours {
+                Some(hours) if band.contains(hours) => (),
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+// MDCA先期分组规则(mdca_rule.json), 键为ADRG编码, 没有规则的ADRG按入组类型判断
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MdcaRule {
+    #[serde(default)]
+    pub any_opt: bool, // 手术表匹配病例的任一手术, 否则只匹配主手术
+    pub vent_hours: Option<ValueBand>, // 有创呼吸机使用时间(小时)满足时无需手术即可入组
+    #[serde(default)]
+    pub vent_opt: HashSet<String>, // 呼吸机相关手术编码, 填写了呼吸机使用时间时需同时满足vent_hours
+}
+
+// MDCY诊断表(MDCY_main_dis_list.json)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MdcyTable {
+    #[serde(default)]
+    pub hiv_dis: HashSet<String>, // HIV诊断
+    #[serde(default)]
+    pub hiv_related_dis: HashSet<String>, // HIV相关疾病
+    #[serde(default)]
+    pub related_adrg: Option<String>, // 以HIV相关疾病进入且未进入其他ADRG时的ADRG
+}
+
+// QY(歧义组)规则(qy_rule.json)
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QyRule {
+    #[serde(default)]
+    pub exempt_adrg: HashSet<String>, // 不判断QY的ADRG(如包含全部手术的ADRG)
+    #[serde(default)]
+    pub qy_adrg: HashMap<String, String>, // 各MDC的QY编码, 缺省为MDC字母加QY
+    #[serde(default)]
+    pub relevant_opt: HashMap<String, HashSet<String>>, // 各MDC相关的手术, 缺省为该MDC下外科与操作ADRG的手术
+}
+
+impl QyRule {
+    // MDC对应的QY编码
+    pub fn qy_code(&self, mdc: &str) -> String {
+        match self.qy_adrg.get(mdc) {
+            Some(code) => code.clone(),
+            None => format!("{}QY", mdc.trim_start_matches("MDC")),
+        }
+    }
+
+    // 为方案中没有给出相关手术的MDC补充该MDC下外科与操作ADRG的手术
+    fn fill_relevant_opt(
+        &mut self,
+        mdc_to_adrg: &HashMap<String, Vec<String>>,
+        adrg_type_dict: &HashMap<String, Vec<String>>,
+        adrg_dis_opt: &HashMap<String, HashSet<String>>,
+    ) {
+        for (mdc, adrg_list) in mdc_to_adrg {
+            if self.relevant_opt.contains_key(mdc) {
+                continue;
+            }
+            let mut opt: HashSet<String> = HashSet::new();
+            for adrg in adrg_list {
+                if adrg_type_dict.get(adrg).is_some_and(|t| t[0] == "内科") {
+                    continue;
+                }
+                let prefix = format!("{}_", adrg);
+                for (key, codes) in adrg_dis_opt {
+                    if key.starts_with(&prefix) && !key.ends_with("_dis") {
+                        opt.extend(codes.iter().cloned());
+                    }
+                }
+            }
+            self.relevant_opt.insert(mdc.clone(), opt);
+        }
+    }
+}
+
+// 并发症细分类型=================================================================================
+// ADRG按并发症细分的方式(adrg_type_dict.json中每项的第二个值)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplicationSplit {
+    Unsplit,     // 无细分, 后缀为9
+    Full,        // 无合并, 后缀为1/3/5
+    MergeMccCc,  // 1合并3, 后缀为3/5
+    MergeCcNone, // 3合并5, 后缀为1/5
+}
+
+impl ComplicationSplit {
+    // 从分组方案中的标签解析
+    pub fn parse(label: &str) -> Option<Self> {
+        match label {
+            "无细分" => Some(Self::Unsplit),
+            "无合并" => Some(Self::Full),
+            "1合并3" => Some(Self::MergeMccCc),
+            "3合并5" => Some(Self::MergeCcNone),
+            _ => None,
+        }
+    }
+
+    // 该细分类型可能产生的DRG后缀
+    pub fn suffixes(&self) -> &'static [&'static str] {
+        match self {
+            Self::Unsplit => &["9"],
+            Self::Full => &["1", "3", "5"],
+            Self::MergeMccCc => &["3", "5"],
+            Self::MergeCcNone => &["1", "5"],
+        }
+    }
+
+    // 根据是否有(未被排除的)MCC、CC确定DRG后缀
+    pub fn suffix(&self, has_mcc: bool, has_cc: bool) -> &'static str {
+        match self {
+            Self::Unsplit => "9",
+            Self::Full if has_mcc => "1",
+            Self::Full if has_cc => "3",
+            Self::MergeMccCc if has_mcc || has_cc => "3",
+            Self::MergeCcNone if has_mcc => "1",
+            _ => "5",
+        }
+    }
+}
+
+// 解析各ADRG的并发症细分类型, 遇到未知标签时报错
+fn parse_complication_split(
+    adrg_type_dict: &HashMap<String, Vec<String>>,
+) -> Result<HashMap<String, ComplicationSplit>, Box<dyn Error>> {
+    let mut split: HashMap<String, ComplicationSplit> = HashMap::new();
+    for (adrg, types) in adrg_type_dict {
+        let label = types.get(1).map(|s| s.as_str()).unwrap_or("");
+        match ComplicationSplit::parse(label) {
+            Some(value) => split.insert(adrg.clone(), value),
+            None => {
+                return Err(format!(
+                    "adrg_type_dict.json: unknown complication split {:?} for {}",
+                    label, adrg
+                )
+                .into())
+            }
+        };
+    }
+    Ok(split)
+}
+
+// CC/MCC与排除表===================================================================================
+// CC/MCC表(cc_mcc_dict.json)中的一项, 兼容["排除表", "CC"]形式, 也可写作{"level": "CC", "tables": [...]}
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "CcMccValue")]
+pub struct CcMccEntry {
+    pub level: String,       // CC或MCC
+    pub tables: Vec<String>, // 所属排除表, 主诊断在其中任一表中时被排除
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CcMccValue {
+    Pair(Vec<String>),
+    Entry {
+        level: String,
+        #[serde(default)]
+        tables: Vec<String>,
+    },
+}
+
+impl From<CcMccValue> for CcMccEntry {
+    fn from(value: CcMccValue) -> Self {
+        match value {
+            // 数组形式的最后一项为级别, 其余为排除表, "无"表示没有排除表
+            CcMccValue::Pair(mut items) => {
+                let level = items.pop().unwrap_or_default();
+                items.retain(|t| !t.is_empty() && t != "无");
+                Self {
+                    level,
+                    tables: items,
+                }
+            }
+            CcMccValue::Entry { level, tables } => Self { level, tables },
+        }
+    }
+}
+
+// 排除表(exclusive_dict.json)中主诊断所属的排除表, 可以是一个表名或表名列表
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExcludeValue {
+    One(String),
+    Many(Vec<String>),
+}
+
+// 解析排除表, 转换为排除表名到主诊断集合的映射
+fn parse_exclude_tables(
+    files: &HashMap<String, Vec<u8>>,
+) -> Result<HashMap<String, HashSet<String>>, Box<dyn Error>> {
+    let raw: HashMap<String, ExcludeValue> = parse_json_table(files, "exclusive_dict.json")?;
+    let mut tables: HashMap<String, HashSet<String>> = HashMap::new();
+    for (dis, value) in raw {
+        let names = match value {
+            ExcludeValue::One(name) => vec![name],
+            ExcludeValue::Many(names) => names,
+        };
+        for name in names {
+            tables.entry(name).or_default().insert(dis.clone());
+        }
+    }
+    Ok(tables)
+}
+
+// 分组方案结构=======================================================================================
+pub struct DrgScheme {
+    pub mdc_dis: HashMap<String, HashSet<String>>, // MDC主诊断表
+    pub mdcz_dis: HashMap<String, HashSet<String>>, // MDCZ诊断表
+    pub adrg_dis_opt: HashMap<String, HashSet<String>>, // ADRG诊断手术表
+    pub adrg_type_dict: HashMap<String, Vec<String>>, // ADRG类型及对应入组类型
+    pub adrg_split: HashMap<String, ComplicationSplit>, // ADRG的并发症细分类型
+    pub mdc_to_adrg: HashMap<String, Vec<String>>, // MDC下的ADRG(按方案中的顺序判断)
+    pub cc_mcc_dict: HashMap<String, CcMccEntry>,  // CCMCC表
+    pub exclude_dict: HashMap<String, HashSet<String>>, // 排除表, 键为排除表名, 值为表中的主诊断
+    pub all_opt_list: HashSet<String>,             // 所有手术列表
+    pub adrg_condition: HashMap<String, AdrgCondition>, // ADRG附加条件
+    pub mdcy_dis: Option<MdcyTable>,               // MDCY诊断表, 没有时按主诊断判断
+    pub mdca_rule: HashMap<String, MdcaRule>,      // MDCA先期分组规则
+    pub qy_rule: QyRule,                           // QY规则
+    pub drg_weight: HashMap<String, f64>,          // DRG权重
+    pub audit_rule: Vec<AuditRule>,                // 编码审核规则
+    pub fingerprint: String,                       // 分组方案指纹, 由全部文件名与内容计算
+}
+
+impl DrgScheme {
+    // 从分组方案目录读取全部分组表
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self, Box<dyn Error>> {
+        let dir = dir.as_ref();
+        let mut files: HashMap<String, Vec<u8>> = HashMap::new();
+        for name in SCHEME_FILES {
+            let bytes = fs::read(dir.join(name)).map_err(|e| format!("{}: {}", name, e))?;
+            files.insert(name.to_string(), bytes);
+        }
+        for name in OPTIONAL_SCHEME_FILES {
+            let path = dir.join(name);
+            if path.exists() {
+                let bytes = fs::read(path).map_err(|e| format!("{}: {}", name, e))?;
+                files.insert(name.to_string(), bytes);
+            }
+        }
+        Self::from_bytes(&files)
+    }
+
+    // 从文件内容构造分组方案, 键为文件名(见SCHEME_FILES与OPTIONAL_SCHEME_FILES), 用于无法访问文件系统的环境
+    pub fn from_bytes(files: &HashMap<String, Vec<u8>>) -> Result<Self, Box<dyn Error>> {
+        // 读取ADRG类型表
+        let adrg_type_dict = parse_json_table(files, "adrg_type_dict.json")?;
+        // 读取MDC对应ADRG表与ADRG诊断手术表, QY规则依赖这两张表
+        let mdc_to_adrg = parse_json_table(files, "mdc_map_adrg.json")?;
+        let adrg_dis_opt = parse_json_table(files, "adrg_dis_opt.json")?;
+        // 读取QY规则
+        let mut qy_rule: QyRule = parse_optional_json_table(files, "qy_rule.json")?;
+        qy_rule.fill_relevant_opt(&mdc_to_adrg, &adrg_type_dict, &adrg_dis_opt);
+        let scheme = Self {
+            // 读取MDC主诊断表
+            mdc_dis: parse_json_table(files, "MDC_main_dis.json")?,
+            // 读取MDCZ主诊断表
+            mdcz_dis: parse_json_table(files, "MDCZ_main_dis_list.json")?,
+            adrg_dis_opt,
+            // 解析并发症细分类型
+            adrg_split: parse_complication_split(&adrg_type_dict)?,
+            adrg_type_dict,
+            mdc_to_adrg,
+            // 读取CCMCC表
+            cc_mcc_dict: parse_json_table(files, "cc_mcc_dict.json")?,
+            // 读取排除表
+            exclude_dict: parse_exclude_tables(files)?,
+            // 读取所有手术列表
+            all_opt_list: parse_icd9_list(scheme_file(files, "all_opt_sheet.txt")?)?,
+            // 读取ADRG附加条件表
+            adrg_condition: parse_optional_json_table(files, "adrg_condition.json")?,
+            // 读取MDCY诊断表
+            mdcy_dis: parse_optional_json_table(files, "MDCY_main_dis_list.json")?,
+            // 读取MDCA先期分组规则
+            mdca_rule: parse_optional_json_table(files, "mdca_rule.json")?,
+            qy_rule,
+            // 读取DRG权重表
+            drg_weight: parse_optional_json_table(files, "drg_weight.json")?,
+            // 读取编码审核规则
+            audit_rule: parse_optional_json_table(files, "audit_rule.json")?,
+            fingerprint: scheme_fingerprint(files),
+        };
+        scheme.check_adrg_references()?;
+        Ok(scheme)
+    }
+
+    // 检查各表引用的ADRG均在ADRG类型表中, 否则分组时无法确定并发症细分
+    fn check_adrg_references(&self) -> Result<(), Box<dyn Error>> {
+        let mut references: Vec<(&str, &String)> = Vec::new();
+        for adrg_list in self.mdc_to_adrg.values() {
+            references.extend(adrg_list.iter().map(|a| ("mdc_map_adrg.json", a)));
+        }
+        references.extend(self.mdca_rule.keys().map(|a| ("mdca_rule.json", a)));
+        if let Some(related_adrg) = self.mdcy_dis.as_ref().and_then(|t| t.related_adrg.as_ref()) {
+            references.push(("MDCY_main_dis_list.json", related_adrg));
+        }
+        for (file, adrg) in references {
+            if !self.adrg_split.contains_key(adrg) {
+                return Err(
+                    format!("{}: ADRG {} is not in adrg_type_dict.json", file, adrg).into(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    // 诊断编码是否出现在分组方案的任一诊断表中
+    pub fn is_known_dis(&self, code: &str) -> bool {
+        self.cc_mcc_dict.contains_key(code)
+            || self.exclude_dict.values().any(|s| s.contains(code))
+            || self.mdc_dis.values().any(|s| s.contains(code))
+            || self.mdcz_dis.values().any(|s| s.contains(code))
+            || self
+                .mdcy_dis
+                .as_ref()
+                .is_some_and(|t| t.hiv_dis.contains(code) || t.hiv_related_dis.contains(code))
+            || self
+                .adrg_dis_opt
+                .iter()
+                .any(|(k, s)| k.ends_with("_dis") && s.contains(code))
+    }
+
+    // 手术编码是否出现在分组方案的任一手术表中
+    pub fn is_known_opt(&self, code: &str) -> bool {
+        self.all_opt_list.contains(code)
+            || self
+                .adrg_dis_opt
+                .iter()
+                .any(|(k, s)| !k.ends_with("_dis") && s.contains(code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fixtures::{scheme, scheme_files};
+
+    #[test]
+    fn unknown_mdcy_related_adrg_is_rejected() {
+        let mut files = scheme_files();
+        files.insert(
+            "MDCY_main_dis_list.json".to_string(),
+            br#"{"hiv_dis": ["B24.x01"], "related_adrg": "YX9"}"#.to_vec(),
+        );
+        let error = DrgScheme::from_bytes(&files).err().unwrap().to_string();
+        assert!(error.contains("YX9"), "{}", error);
+    }
+
+    #[test]
+    fn cc_mcc_entry_accepts_pair_and_table_list() {
+        let entries: HashMap<String, CcMccEntry> = serde_json::from_str(
+            r#"{"A": ["表7-1", "CC"], "B": ["无", "MCC"], "C": {"level": "CC", "tables": ["T1", "T2"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(entries["A"].level, "CC");
+        assert_eq!(entries["A"].tables, vec!["表7-1".to_string()]);
+        assert_eq!(entries["B"].level, "MCC");
+        assert!(entries["B"].tables.is_empty());
+        assert_eq!(
+            entries["C"].tables,
+            vec!["T1".to_string(), "T2".to_string()]
+        );
+    }
+
+    #[test]
+    fn principal_may_belong_to_several_exclusion_tables() {
+        let files = HashMap::from([(
+            "exclusive_dict.json".to_string(),
+            r#"{"A00.000": "T1", "B00.000": ["T1", "T2"]}"#.as_bytes().to_vec(),
+        )]);
+        let tables = parse_exclude_tables(&files).unwrap();
+        assert_eq!(tables["T1"].len(), 2);
+        assert_eq!(tables["T2"], HashSet::from(["B00.000".to_string()]));
+    }
+
+    #[test]
+    fn complication_split_labels() {
+        assert_eq!(
+            ComplicationSplit::parse("无细分"),
+            Some(ComplicationSplit::Unsplit)
+        );
+        assert_eq!(
+            ComplicationSplit::parse("无合并"),
+            Some(ComplicationSplit::Full)
+        );
+        assert_eq!(
+            ComplicationSplit::parse("1合并3"),
+            Some(ComplicationSplit::MergeMccCc)
+        );
+        assert_eq!(
+            ComplicationSplit::parse("3合并5"),
+            Some(ComplicationSplit::MergeCcNone)
+        );
+        assert_eq!(ComplicationSplit::parse("未细分"), None);
+
+        let mut files = scheme_files();
+        files.insert(
+            "adrg_type_dict.json".to_string(),
+            r#"{"AA1": ["外科", "1合并5", "common_opt"]}"#.as_bytes().to_vec(),
+        );
+        let error = DrgScheme::from_bytes(&files).err().unwrap().to_string();
+        assert!(error.contains("1合并5"), "{}", error);
+    }
+
+    #[test]
+    fn complication_split_suffixes() {
+        use ComplicationSplit::*;
+        // (MCC, CC)依次为(有, 无)、(无, 有)、(无, 无)时的后缀
+        let cases = [
+            (Unsplit, ["9", "9", "9"]),
+            (Full, ["1", "3", "5"]),
+            (MergeMccCc, ["3", "3", "5"]),
+            (MergeCcNone, ["1", "5", "5"]),
+        ];
+        for (split, expected) in cases {
+            let actual = [
+                split.suffix(true, false),
+                split.suffix(false, true),
+                split.suffix(false, false),
+            ];
+            assert_eq!(actual, expected, "{:?}", split);
+            assert_eq!(split.suffix(true, true), expected[0], "{:?}", split);
+            assert!(actual.iter().all(|s| split.suffixes().contains(s)));
+        }
+    }
+
+    #[test]
+    fn qy_rule_defaults() {
+        let rule = QyRule::default();
+        assert_eq!(rule.qy_code("MDCD"), "DQY");
+        assert_eq!(rule.qy_code("MDCS"), "SQY");
+
+        let scheme = scheme();
+        assert_eq!(scheme.qy_rule.qy_code("MDCD"), "DQY");
+        // 未配置相关手术时取该MDC外科与操作ADRG的手术
+        let relevant = &scheme.qy_rule.relevant_opt["MDCE"];
+        assert!(relevant.contains("32.2904"));
+        assert!(!relevant.contains("39.9014"));
+    }
+
+    #[test]
+    fn content_hash_is_stable() {
+        // 固定值保证存储文件中的哈希跨版本可用
+        assert_eq!(content_hash([]), "cbf29ce484222325");
+        assert_eq!(content_hash([b"".as_slice()]), "a8c7f832281a39c5");
+        assert_eq!(content_hash([b"a".as_slice()]), "529a4ddc8ff56bbf");
+        assert_eq!(content_hash(["结算流水号".as_bytes()]), "2a1bad3101785874");
+        // 各部分以长度分隔
+        assert_ne!(
+            content_hash([b"ab".as_slice(), b"c".as_slice()]),
+            content_hash([b"a".as_slice(), b"bc".as_slice()])
+        );
+    }
+
+    #[test]
+    fn fingerprint_tracks_file_contents() {
+        let mut files = scheme_files();
+        let fingerprint = scheme_fingerprint(&files);
+        assert_eq!(scheme().fingerprint, fingerprint);
+        files
+            .get_mut("qy_rule.json")
+            .unwrap()
+            .extend_from_slice(b" ");
+        assert_ne!(scheme_fingerprint(&files), fingerprint);
+    }
+}
@@ -0,0 +1,306 @@
+use crate::scheme::DrgScheme;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::string::String;
+
+// 变更日志中每个列表最多列出的编码数
+const MAX_LISTED_CODES: usize = 20;
+
+// 列出编码, 过多时只列出前MAX_LISTED_CODES个
+fn list_codes(codes: &BTreeSet<&String>) -> String {
+    let mut listed: Vec<&str> = codes
+        .iter()
+        .take(MAX_LISTED_CODES)
+        .map(|c| c.as_str())
+        .collect();
+    if codes.len() > MAX_LISTED_CODES {
+        listed.push("...");
+    }
+    listed.join(", ")
+}
+
+// 比较两个编码集合, 输出增加与删除的编码
+fn diff_codes(
+    log: &mut Vec<String>,
+    name: &str,
+    old: Option<&HashSet<String>>,
+    new: Option<&HashSet<String>>,
+) {
+    let empty = HashSet::new();
+    let old = old.unwrap_or(&empty);
+    let new = new.unwrap_or(&empty);
+    let added: BTreeSet<&String> = new.difference(old).collect();
+    let removed: BTreeSet<&String> = old.difference(new).collect();
+    if !added.is_empty() {
+        log.push(format!(
+            "  {}: +{} {}",
+            name,
+            added.len(),
+            list_codes(&added)
+        ));
+    }
+    if !removed.is_empty() {
+        log.push(format!(
+            "  {}: -{} {}",
+            name,
+            removed.len(),
+            list_codes(&removed)
+        ));
+    }
+}
+
+// ADRG的诊断手术表内容, 键为去掉ADRG编码后的表名(如_dis、_opt1), 用于识别改名的ADRG
+fn adrg_signature(scheme: &DrgScheme, adrg: &str) -> BTreeMap<String, BTreeSet<String>> {
+    let prefix = format!("{}_", adrg);
+    scheme
+        .adrg_dis_opt
+        .iter()
+        .filter(|(key, _)| key.starts_with(&prefix))
+        .map(|(key, codes)| {
+            (
+                key[adrg.len()..].to_string(),
+                codes.iter().cloned().collect(),
+            )
+        })
+        .collect()
+}
+
+// mdc_map_adrg.json: 各MDC下增加、删除与改名的ADRG
+fn diff_mdc_to_adrg(log: &mut Vec<String>, old: &DrgScheme, new: &DrgScheme) {
+    log.push("mdc_map_adrg.json".to_string());
+    let mdc_list: BTreeSet<&String> = old
+        .mdc_to_adrg
+        .keys()
+        .chain(new.mdc_to_adrg.keys())
+        .collect();
+    for mdc in mdc_list {
+        let (old_adrg, new_adrg) = match (old.mdc_to_adrg.get(mdc), new.mdc_to_adrg.get(mdc)) {
+            (Some(o), Some(n)) => (o, n),
+            (Some(_), None) => {
+                log.push(format!("  {} removed", mdc));
+                continue;
+            }
+            (None, Some(_)) => {
+                log.push(format!("  {} added", mdc));
+                continue;
+            }
+            (None, None) => continue,
+        };
+        let mut added: Vec<&String> = new_adrg.iter().filter(|a| !old_adrg.contains(a)).collect();
+        let mut removed: Vec<&String> = old_adrg.iter().filter(|a| !new_adrg.contains(a)).collect();
+        // 删除的ADRG与增加的ADRG诊断手术表完全相同时视为改名
+        let mut renamed: Vec<(&String, &String)> = Vec::new();
+        removed.retain(|r| {
+            let signature = adrg_signature(old, r);
+            if signature.is_empty() {
+                return true;
+            }
+            match added
+                .iter()
+                .position(|a| adrg_signature(new, a) == signature)
+            {
+                Some(idx) => {
+                    renamed.push((r, added.remove(idx)));
+                    false
+                }
+                None => true,
+            }
+        });
+        for adrg in added {
+            log.push(format!("  {}: ADRG {} added", mdc, adrg));
+        }
+        for adrg in removed {
+            log.push(format!("  {}: ADRG {} removed", mdc, adrg));
+        }
+        for (from, to) in renamed {
+            log.push(format!("  {}: ADRG {} renamed to {}", mdc, from, to));
+        }
+    }
+}
+
+// adrg_type_dict.json: ADRG类型、并发症细分与入组类型的变化
+fn diff_adrg_type(log: &mut Vec<String>, old: &DrgScheme, new: &DrgScheme) {
+    log.push("adrg_type_dict.json".to_string());
+    let fields = ["type", "complication split", "entry type"];
+    let adrg_list: BTreeSet<&String> = old
+        .adrg_type_dict
+        .keys()
+        .chain(new.adrg_type_dict.keys())
+        .collect();
+    for adrg in adrg_list {
+        let (o, n) = match (old.adrg_type_dict.get(adrg), new.adrg_type_dict.get(adrg)) {
+            (Some(o), Some(n)) => (o, n),
+            (Some(o), None) => {
+                log.push(format!("  {}: removed ({})", adrg, o.join(", ")));
+                continue;
+            }
+            (None, Some(n)) => {
+                log.push(format!("  {}: added ({})", adrg, n.join(", ")));
+                continue;
+            }
+            (None, None) => continue,
+        };
+        for (idx, field) in fields.iter().enumerate() {
+            if o.get(idx) != n.get(idx) {
+                log.push(format!(
+                    "  {}: {} {} -> {}",
+                    adrg,
+                    field,
+                    o.get(idx).map(|s| s.as_str()).unwrap_or(""),
+                    n.get(idx).map(|s| s.as_str()).unwrap_or("")
+                ));
+            }
+        }
+    }
+}
+
+// adrg_dis_opt.json: 各诊断、手术表增加与删除的编码
+fn diff_adrg_dis_opt(log: &mut Vec<String>, old: &DrgScheme, new: &DrgScheme) {
+    log.push("adrg_dis_opt.json".to_string());
+    let keys: BTreeSet<&String> = old
+        .adrg_dis_opt
+        .keys()
+        .chain(new.adrg_dis_opt.keys())
+        .collect();
+    for key in keys {
+        diff_codes(
+            log,
+            key,
+            old.adrg_dis_opt.get(key),
+            new.adrg_dis_opt.get(key),
+        );
+    }
+}
+
+// cc_mcc_dict.json: CC/MCC的增加、删除、级别与排除表变化
+fn diff_cc_mcc(log: &mut Vec<String>, old: &DrgScheme, new: &DrgScheme) {
+    log.push("cc_mcc_dict.json".to_string());
+    let codes: BTreeSet<&String> = old
+        .cc_mcc_dict
+        .keys()
+        .chain(new.cc_mcc_dict.keys())
+        .collect();
+    for code in codes {
+        match (old.cc_mcc_dict.get(code), new.cc_mcc_dict.get(code)) {
+            (Some(o), None) => log.push(format!("  {}: {} removed", code, o.level)),
+            (None, Some(n)) => log.push(format!("  {}: {} added", code, n.level)),
+            (Some(o), Some(n)) => {
+                if o.level != n.level {
+                    log.push(format!("  {}: level {} -> {}", code, o.level, n.level));
+                }
+                // 排除表的顺序不影响分组, 按集合比较
+                let old_tables: BTreeSet<&String> = o.tables.iter().collect();
+                let new_tables: BTreeSet<&String> = n.tables.iter().collect();
+                if old_tables != new_tables {
+                    log.push(format!(
+                        "  {}: exclusion tables {} -> {}",
+                        code,
+                        list_codes(&old_tables),
+                        list_codes(&new_tables)
+                    ));
+                }
+            }
+            (None, None) => (),
+        }
+    }
+}
+
+// exclusive_dict.json: 各排除表增加与删除的主诊断
+fn diff_exclude(log: &mut Vec<String>, old: &DrgScheme, new: &DrgScheme) {
+    log.push("exclusive_dict.json".to_string());
+    let tables: BTreeSet<&String> = old
+        .exclude_dict
+        .keys()
+        .chain(new.exclude_dict.keys())
+        .collect();
+    for table in tables {
+        diff_codes(
+            log,
+            table,
+            old.exclude_dict.get(table),
+            new.exclude_dict.get(table),
+        );
+    }
+}
+
+// 逐表比较两套分组方案, 返回变更日志, 每行一条, 各表以文件名开头
+pub fn diff_schemes(old: &DrgScheme, new: &DrgScheme) -> Vec<String> {
+    let mut log: Vec<String> = Vec::new();
+    diff_mdc_to_adrg(&mut log, old, new);
+    diff_adrg_type(&mut log, old, new);
+    diff_adrg_dis_opt(&mut log, old, new);
+    diff_cc_mcc(&mut log, old, new);
+    diff_exclude(&mut log, old, new);
+    log
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::scheme;
+
+    #[test]
+    fn adrg_type_additions_and_removals_are_logged() {
+        let old = scheme();
+        let mut new = scheme();
+        let removed = new.adrg_type_dict.remove("YR2").unwrap();
+        new.adrg_type_dict.insert("YR9".to_string(), removed);
+        new.adrg_type_dict.get_mut("YR1").unwrap()[1] = "无细分".to_string();
+        let log = diff_schemes(&old, &new);
+        for line in [
+            "  YR2: removed (内科, 无合并, common_dis)",
+            "  YR9: added (内科, 无合并, common_dis)",
+            "  YR1: complication split 无合并 -> 无细分",
+        ] {
+            assert!(log.iter().any(|l| l == line), "{} not in {:?}", line, log);
+        }
+    }
+
+    #[test]
+    fn adrg_with_same_tables_under_new_code_is_a_rename() {
+        let old = scheme();
+        let mut new = scheme();
+        let adrg_list = new.mdc_to_adrg.get_mut("MDCE").unwrap();
+        let idx = adrg_list.iter().position(|a| a == "EX2").unwrap();
+        adrg_list[idx] = "EX9".to_string();
+        let codes = new.adrg_dis_opt.remove("EX2_dis").unwrap();
+        new.adrg_dis_opt.insert("EX9_dis".to_string(), codes);
+        let log = diff_schemes(&old, &new);
+        assert!(
+            log.iter().any(|l| l == "  MDCE: ADRG EX2 renamed to EX9"),
+            "{:?}",
+            log
+        );
+        assert!(!log.iter().any(|l| l.contains("ADRG EX2 removed")));
+        assert!(!log.iter().any(|l| l.contains("ADRG EX9 added")));
+    }
+
+    #[test]
+    fn exclusion_tables_compare_regardless_of_order() {
+        let code = "E87.102".to_string();
+        let mut old = scheme();
+        let mut new = scheme();
+        old.cc_mcc_dict.get_mut(&code).unwrap().tables = vec!["T1".to_string(), "T2".to_string()];
+        new.cc_mcc_dict.get_mut(&code).unwrap().tables = vec!["T2".to_string(), "T1".to_string()];
+        let log = diff_schemes(&old, &new);
+        assert!(
+            !log.iter().any(|l| l.contains("exclusion tables")),
+            "{:?}",
+            log
+        );
+        new.cc_mcc_dict.get_mut(&code).unwrap().tables.pop();
+        let log = diff_schemes(&old, &new);
+        assert!(
+            log.iter()
+                .any(|l| l.starts_with(&format!("  {}: exclusion tables", code))),
+            "{:?}",
+            log
+        );
+    }
+
+    #[test]
+    fn identical_schemes_have_no_changes() {
+        let scheme = scheme();
+        let log = diff_schemes(&scheme, &scheme);
+        assert!(log.iter().all(|l| !l.starts_with("  ")), "{:?}", log);
+    }
+}
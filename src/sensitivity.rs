@@ -0,0 +1,186 @@
+use crate::case::DrgCase;
+use crate::group::{assign_drg, DrgGrouping};
+use crate::scheme::DrgScheme;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::string::String;
+
+// 去掉一个编码后的分组结果
+#[derive(Debug, Clone, Serialize)]
+pub struct Removal {
+    pub code: String, // 去掉的编码
+    pub is_dis: bool, // 是否为诊断(否则为手术)
+    pub drg: String,  // 去掉后的分组结果
+    pub drops: bool,  // 分组或并发症级别是否降低
+}
+
+// 单个病例的高编敏感性分析结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SensitivityReport {
+    pub id: String,                    // 病例ID
+    pub hospital_code: Option<String>, // 定点医疗机构代码
+    pub department: Option<String>,    // 出院科别
+    pub drg: String,                   // 原分组结果
+    pub removals: Vec<Removal>,        // 逐一去掉编码后的分组结果
+}
+
+impl SensitivityReport {
+    // 单独决定分组(MCC/CC或手术组)的编码
+    pub fn carriers(&self) -> Vec<&Removal> {
+        self.removals.iter().filter(|r| r.drops).collect()
+    }
+}
+
+// 并发症级别, 越大越高, 无细分返回None
+fn complication_rank(drg: &str) -> Option<u8> {
+    match drg.chars().last() {
+        Some('1') => Some(3),
+        Some('3') => Some(2),
+        Some('5') => Some(1),
+        _ => None,
+    }
+}
+
+// ADRG是否为外科或操作组
+fn is_procedure_adrg(scheme: &DrgScheme, result: &DrgGrouping) -> bool {
+    result.qy_operations.is_empty()
+        && scheme
+            .adrg_type_dict
+            .get(&result.adrg)
+            .is_some_and(|t| t[0] != "内科")
+}
+
+// 分组结果是否低于原分组: 有权重时比较权重, 否则同一ADRG比较并发症级别, 不同ADRG比较是否失去外科或操作组
+fn is_drop(scheme: &DrgScheme, base: &DrgGrouping, new: &DrgGrouping) -> bool {
+    if let (Some(w0), Some(w1)) = (
+        scheme.drg_weight.get(&base.drg),
+        scheme.drg_weight.get(&new.drg),
+    ) {
+        return w1 < w0;
+    }
+    if base.adrg == new.adrg {
+        return match (complication_rank(&base.drg), complication_rank(&new.drg)) {
+            (Some(r0), Some(r1)) => r1 < r0,
+            _ => false,
+        };
+    }
+    is_procedure_adrg(scheme, base) && !is_procedure_adrg(scheme, new)
+}
+
+// 逐一去掉每个其他诊断与每个手术后重新分组, 判断分组是否降低
+pub fn analyze_case(record: &DrgCase, scheme: &DrgScheme) -> SensitivityReport {
+    let base = assign_drg(record, scheme);
+    let mut removals: Vec<Removal> = Vec::new();
+    let mut push = |variant: DrgCase, code: &str, is_dis: bool| {
+        let result = assign_drg(&variant, scheme);
+        removals.push(Removal {
+            code: code.to_string(),
+            is_dis,
+            drops: is_drop(scheme, &base, &result),
+            drg: result.drg,
+        });
+    };
+    for (idx, code) in record.other_dis.iter().enumerate() {
+        let mut variant = record.clone();
+        variant.other_dis.remove(idx);
+        variant.recombine();
+        push(variant, code, true);
+    }
+    // 去掉主手术时以第一个其他手术为主手术
+    if !record.main_opt.is_empty() {
+        let mut variant = record.clone();
+        variant.main_opt = if variant.other_opt.is_empty() {
+            String::new()
+        } else {
+            variant.other_opt.remove(0)
+        };
+        variant.recombine();
+        push(variant, &record.main_opt, false);
+    }
+    for (idx, code) in record.other_opt.iter().enumerate() {
+        let mut variant = record.clone();
+        variant.other_opt.remove(idx);
+        variant.recombine();
+        push(variant, code, false);
+    }
+    SensitivityReport {
+        id: record.id.clone(),
+        hospital_code: record.hospital_code.clone(),
+        department: record.department.clone(),
+        drg: base.drg,
+        removals,
+    }
+}
+
+// 按医疗机构与科室汇总的敏感性分析结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SensitivitySummary {
+    pub hospital_code: String,  // 定点医疗机构代码
+    pub department: String,     // 出院科别
+    pub cases: usize,           // 病例数
+    pub sensitive_cases: usize, // 去掉一个编码即降低分组的病例数
+    pub dis_carriers: usize,    // 决定分组的诊断数
+    pub opt_carriers: usize,    // 决定分组的手术数
+}
+
+impl SensitivitySummary {
+    // 敏感病例占比
+    pub fn sensitive_rate(&self) -> f64 {
+        if self.cases == 0 {
+            0.0
+        } else {
+            self.sensitive_cases as f64 / self.cases as f64
+        }
+    }
+}
+
+// 按医疗机构与科室汇总, 未填写时记为空字符串
+pub fn summarize(reports: &[SensitivityReport]) -> Vec<SensitivitySummary> {
+    let mut groups: BTreeMap<(String, String), SensitivitySummary> = BTreeMap::new();
+    for report in reports {
+        let key = (
+            report.hospital_code.clone().unwrap_or_default(),
+            report.department.clone().unwrap_or_default(),
+        );
+        let summary = groups.entry(key.clone()).or_insert(SensitivitySummary {
+            hospital_code: key.0,
+            department: key.1,
+            ..Default::default()
+        });
+        let carriers = report.carriers();
+        summary.cases += 1;
+        if !carriers.is_empty() {
+            summary.sensitive_cases += 1;
+        }
+        summary.dis_carriers += carriers.iter().filter(|r| r.is_dis).count();
+        summary.opt_carriers += carriers.iter().filter(|r| !r.is_dis).count();
+    }
+    groups.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{sample_case, scheme};
+
+    #[test]
+    fn finds_codes_carrying_the_group() {
+        // E87.102为CC, I10.x00不是; 32.2904决定外科组EB2
+        let record = sample_case("J20.900", &["E87.102", "I10.x00"], &["32.2904"], 50.0);
+        let report = analyze_case(&record, &scheme());
+        assert_eq!(report.drg, "EB23");
+        let carriers: Vec<(&str, &str)> = report
+            .carriers()
+            .iter()
+            .map(|r| (r.code.as_str(), r.drg.as_str()))
+            .collect();
+        assert_eq!(carriers, vec![("E87.102", "EB25"), ("32.2904", "EX23")]);
+        let other = report
+            .removals
+            .iter()
+            .find(|r| r.code == "I10.x00")
+            .unwrap();
+        assert!(!other.drops);
+        assert_eq!(other.drg, "EB23");
+    }
+}
@@ -0,0 +1,187 @@
+use crate::audit::format_findings;
+use crate::batch::construct_drg_case;
+use crate::group::group_case;
+use crate::scheme::DrgScheme;
+use polars::prelude::*;
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection};
+use std::error::Error;
+use std::string::String;
+
+// 为表名或列名加上双引号
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// 数据来源是表名还是查询语句
+fn source_query(source: &str) -> String {
+    let lower = source.trim_start().to_lowercase();
+    if lower.starts_with("select") || lower.starts_with("with") {
+        source.to_string()
+    } else {
+        format!("SELECT * FROM {}", quote_ident(source.trim()))
+    }
+}
+
+// 某一列的取值, 按首个非空值确定列类型
+enum ColumnValues {
+    Int(Vec<Option<i64>>),
+    Float(Vec<Option<f64>>),
+    Text(Vec<Option<String>>),
+}
+
+impl ColumnValues {
+    fn push(&mut self, value: ValueRef) {
+        // 整数列遇到小数时转为浮点数列, 遇到文本时转为文本列
+        match (&mut *self, value) {
+            (Self::Int(v), ValueRef::Real(f)) => {
+                let mut floats: Vec<Option<f64>> = v.iter().map(|i| i.map(|i| i as f64)).collect();
+                floats.push(Some(f));
+                *self = Self::Float(floats);
+            }
+            (Self::Int(_) | Self::Float(_), ValueRef::Text(_)) => {
+                let mut texts = self.to_text();
+                texts.push(text_value(value));
+                *self = Self::Text(texts);
+            }
+            (Self::Int(v), _) => v.push(match value {
+                ValueRef::Integer(i) => Some(i),
+                _ => None,
+            }),
+            (Self::Float(v), _) => v.push(match value {
+                ValueRef::Integer(i) => Some(i as f64),
+                ValueRef::Real(f) => Some(f),
+                _ => None,
+            }),
+            (Self::Text(v), _) => v.push(text_value(value)),
+        }
+    }
+
+    fn to_text(&self) -> Vec<Option<String>> {
+        match self {
+            Self::Int(v) => v.iter().map(|i| i.map(|i| i.to_string())).collect(),
+            Self::Float(v) => v.iter().map(|f| f.map(|f| f.to_string())).collect(),
+            Self::Text(v) => v.clone(),
+        }
+    }
+
+    fn into_series(self, name: &str) -> Series {
+        match self {
+            Self::Int(v) => Series::new(name, v),
+            Self::Float(v) => Series::new(name, v),
+            Self::Text(v) => Series::new(name, v),
+        }
+    }
+}
+
+// 将SQLite的值转换为文本, NULL与二进制数据为None
+fn text_value(value: ValueRef) -> Option<String> {
+    match value {
+        ValueRef::Integer(i) => Some(i.to_string()),
+        ValueRef::Real(f) => Some(f.to_string()),
+        ValueRef::Text(t) => Some(String::from_utf8_lossy(t).to_string()),
+        _ => None,
+    }
+}
+
+// 从SQLite的表或查询读取病例表格, 列名与CSV表格一致
+pub fn from_sqlite(db_path: &str, source: &str) -> Result<DataFrame, Box<dyn Error>> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(&source_query(source))?;
+    let names: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+    let mut columns: Vec<Option<ColumnValues>> = names.iter().map(|_| None).collect();
+    let mut rows = stmt.query([])?;
+    let mut height = 0;
+    while let Some(row) = rows.next()? {
+        for (idx, column) in columns.iter_mut().enumerate() {
+            let value = row.get_ref(idx)?;
+            if column.is_none() {
+                // 首个非空值之前的行均为空值
+                *column = match value {
+                    ValueRef::Integer(_) => Some(ColumnValues::Int(vec![None; height])),
+                    ValueRef::Real(_) => Some(ColumnValues::Float(vec![None; height])),
+                    ValueRef::Text(_) => Some(ColumnValues::Text(vec![None; height])),
+                    _ => None,
+                };
+            }
+            if let Some(c) = column {
+                c.push(value);
+            }
+        }
+        height += 1;
+    }
+    let series: Vec<Series> = names
+        .iter()
+        .zip(columns)
+        .map(|(name, column)| match column {
+            Some(c) => c.into_series(name),
+            // 全部为空值的列
+            None => Series::new(name, vec![None::<String>; height]),
+        })
+        .collect();
+    Ok(DataFrame::new(series)?)
+}
+
+// 从SQLite读取病例分组, 将分组结果写入结果表(按结算流水号覆盖)
+pub fn drg_group_sqlite(
+    db_path: &str,
+    source: &str,        // 表名或查询语句
+    results_table: &str, // 结果表名, 不存在时自动创建
+    scheme: &DrgScheme,  // 分组方案
+) -> Result<(), Box<dyn Error>> {
+    println!("reading data ... ");
+    let df = from_sqlite(db_path, source)?;
+    println!("drg grouping ... ");
+    let drg_case = construct_drg_case(&df)?;
+
+    println!("data exporting ...");
+    let mut conn = Connection::open(db_path)?;
+    let table = quote_ident(results_table);
+    conn.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (结算流水号 TEXT PRIMARY KEY, clear_code TEXT, audit TEXT)",
+            table
+        ),
+        [],
+    )?;
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare(&format!(
+            "INSERT OR REPLACE INTO {} (结算流水号, clear_code, audit) VALUES (?1, ?2, ?3)",
+            table
+        ))?;
+        for case in &drg_case {
+            let result = group_case(case, scheme);
+            stmt.execute(params![case.id, result.drg, format_findings(&result.audit)])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{sample_db, scheme};
+
+    #[test]
+    fn results_keep_source_id() {
+        let db = sample_db("round_trip.db");
+        let scheme = scheme();
+        drg_group_sqlite(&db, "cases", "results", &scheme).unwrap();
+        let conn = Connection::open(&db).unwrap();
+        let joined: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM cases JOIN results USING (结算流水号)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let id: String = conn
+            .query_row("SELECT 结算流水号 FROM results", [], |row| row.get(0))
+            .unwrap();
+        std::fs::remove_file(&db).unwrap();
+        assert_eq!(id, "A001");
+        assert_eq!(joined, 1);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::case::DrgCase;
+use crate::group::{group_case, DrgResult};
+use crate::scheme::DrgScheme;
+use js_sys::{ArrayBuffer, Object, Uint8Array};
+use std::collections::HashMap;
+use std::string::String;
+use wasm_bindgen::prelude::*;
+
+// 分组器, 持有一套分组方案
+#[wasm_bindgen]
+pub struct Grouper {
+    scheme: DrgScheme,
+}
+
+#[wasm_bindgen]
+impl Grouper {
+    // 从分组方案文件内容初始化分组器, files为{文件名: Uint8Array或字符串}
+    #[wasm_bindgen(constructor)]
+    pub fn new(files: &Object) -> Result<Grouper, JsError> {
+        let mut contents: HashMap<String, Vec<u8>> = HashMap::new();
+        for entry in Object::entries(files).iter() {
+            let pair = js_sys::Array::from(&entry);
+            let name = pair
+                .get(0)
+                .as_string()
+                .ok_or_else(|| JsError::new("scheme file name must be a string"))?;
+            let value = pair.get(1);
+            let bytes = if let Some(text) = value.as_string() {
+                text.into_bytes()
+            } else if value.is_instance_of::<Uint8Array>() || value.is_instance_of::<ArrayBuffer>()
+            {
+                Uint8Array::new(&value).to_vec()
+            } else {
+                return Err(JsError::new(&format!(
+                    "scheme file {} must be a string, Uint8Array or ArrayBuffer",
+                    name
+                )));
+            };
+            contents.insert(name, bytes);
+        }
+        let scheme = DrgScheme::from_bytes(&contents).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Grouper { scheme })
+    }
+
+    // 对单个病例进行分组, 返回结构化的分组结果(字段同管道模式的输出)
+    pub fn group(&self, case: JsValue) -> Result<JsValue, JsError> {
+        let record: DrgCase = serde_wasm_bindgen::from_value(case)?;
+        Ok(serde_wasm_bindgen::to_value(&self.group_record(record))?)
+    }
+}
+
+impl Grouper {
+    // 统一编码格式后分组, 与管道模式的处理相同
+    fn group_record(&self, mut record: DrgCase) -> DrgResult {
+        record.normalize();
+        group_case(&record, &self.scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::scheme;
+
+    #[test]
+    fn groups_case_object() {
+        let grouper = Grouper { scheme: scheme() };
+        // 与JS对象相同的字段, 编码格式不统一
+        let record: DrgCase = serde_json::from_value(serde_json::json!({
+            "id": "1", "main_dis": " j20.900", "other_dis": ["E87.102", ""],
+            "sex": "男", "age": 29.0, "weight": 2789
+        }))
+        .unwrap();
+        let result = serde_json::to_value(grouper.group_record(record)).unwrap();
+        assert_eq!(result["drg"], "EX23");
+        let mut keys: Vec<&str> = result
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "adrg",
+                "audit",
+                "complications",
+                "drg",
+                "id",
+                "mdc",
+                "qy_operations",
+                "trauma_sites"
+            ]
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use crate::batch::{drg_group_file, BatchSummary};
+use crate::scheme::DrgScheme;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::string::String;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// 监视目录的配置
+pub struct WatchConfig {
+    pub watch_dir: PathBuf,   // 监视的目录, 只处理其中的CSV文件
+    pub out_dir: PathBuf,     // 分组结果目录, 同时写出日志watch.log
+    pub archive_dir: PathBuf, // 处理成功的输入文件移入的目录
+    pub error_dir: PathBuf,   // 处理失败的输入文件移入的目录
+    pub interval: Duration,   // 扫描间隔
+}
+
+impl WatchConfig {
+    // 归档与错误目录默认为监视目录下的archive与error
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(watch_dir: P, out_dir: Q) -> Self {
+        let watch_dir = watch_dir.as_ref().to_path_buf();
+        Self {
+            archive_dir: watch_dir.join("archive"),
+            error_dir: watch_dir.join("error"),
+            out_dir: out_dir.as_ref().to_path_buf(),
+            watch_dir,
+            interval: Duration::from_secs(5),
+        }
+    }
+}
+
+// 当前时间(Unix时间戳, 秒)
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// 写出一行日志到标准输出与日志文件
+fn log_line(config: &WatchConfig, message: &str) {
+    let line = format!("[{}] {}", unix_time(), message);
+    println!("{}", line);
+    let log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(config.out_dir.join("watch.log"));
+    if let Ok(mut file) = log_file {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+// 将文件移入目录, 同名文件已存在时在文件名前加上时间戳
+fn move_into(path: &Path, dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let name = path.file_name().ok_or("invalid file name")?;
+    let mut target = dir.join(name);
+    if target.exists() {
+        target = dir.join(format!("{}_{}", unix_time(), name.to_string_lossy()));
+    }
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+// 监视目录中的CSV文件及其大小
+fn list_csv_files(dir: &Path) -> Result<HashMap<PathBuf, u64>, Box<dyn Error>> {
+    let mut files: HashMap<PathBuf, u64> = HashMap::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        if is_csv && entry.file_type()?.is_file() {
+            files.insert(path, entry.metadata()?.len());
+        }
+    }
+    Ok(files)
+}
+
+// 分组一个文件, 错误与panic均视为处理失败
+fn process_file(
+    config: &WatchConfig,
+    path: &Path,
+    scheme: &DrgScheme,
+) -> Result<(PathBuf, BatchSummary), String> {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let out_path = config.out_dir.join(format!("{}_result.csv", stem));
+    let in_path = path.to_string_lossy().to_string();
+    let out_str = out_path.to_string_lossy().to_string();
+    match catch_unwind(AssertUnwindSafe(|| {
+        drg_group_file(&in_path, &out_str, scheme, false).map_err(|e| e.to_string())
+    })) {
+        Ok(Ok(summary)) => Ok((out_path, summary)),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err("drg grouping panicked".to_string()),
+    }
+}
+
+// 处理一个已经写入完成的文件, 成功时移入归档目录, 失败时移入错误目录并记录错误;
+// 返回输入文件是否已经移出监视目录
+fn handle_file(config: &WatchConfig, path: &Path, scheme: &DrgScheme) -> bool {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let start = Instant::now();
+    let outcome = process_file(config, path, scheme);
+    let elapsed = start.elapsed().as_secs_f64();
+    let (dir, message) = match outcome {
+        Ok((out_path, summary)) => (
+            &config.archive_dir,
+            format!(
+                "{}: ok, {} cases, {} ungrouped, {} with audit issues, {:.2}s -> {}",
+                name,
+                summary.cases,
+                summary.ungrouped,
+                summary.audit_issues,
+                elapsed,
+                out_path.display()
+            ),
+        ),
+        Err(e) => (&config.error_dir, format!("{}: failed, {}", name, e)),
+    };
+    log_line(config, &message);
+    match move_into(path, dir) {
+        Ok(_) => true,
+        Err(e) => {
+            log_line(
+                config,
+                &format!(
+                    "{}: cannot move input file, {}; skipped until it changes",
+                    name, e
+                ),
+            );
+            false
+        }
+    }
+}
+
+// 两次扫描之间保留的状态
+#[derive(Default)]
+struct WatchState {
+    last_sizes: HashMap<PathBuf, u64>, // 上次扫描时的文件大小
+    stuck: HashMap<PathBuf, u64>,      // 已处理但无法移出的文件及其大小, 大小不变时不再处理
+}
+
+// 扫描一次监视目录, 处理写入完成的文件, 返回本次处理的文件
+fn scan(
+    config: &WatchConfig,
+    scheme: &DrgScheme,
+    state: &mut WatchState,
+) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let sizes = list_csv_files(&config.watch_dir)?;
+    state
+        .stuck
+        .retain(|path, size| sizes.get(path) == Some(size));
+    let mut ready: Vec<PathBuf> = sizes
+        .iter()
+        .filter(|(path, size)| {
+            state.last_sizes.get(*path) == Some(size) && !state.stuck.contains_key(*path)
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    ready.sort();
+    for path in &ready {
+        if !handle_file(config, path, scheme) {
+            state.stuck.insert(path.clone(), sizes[path]);
+        }
+    }
+    state.last_sizes = sizes;
+    Ok(ready)
+}
+
+// 持续监视目录, 对新出现的CSV文件分组; 文件大小在两次扫描之间不变才视为写入完成
+pub fn watch_folder(config: &WatchConfig, scheme: &DrgScheme) -> Result<(), Box<dyn Error>> {
+    for dir in [&config.out_dir, &config.archive_dir, &config.error_dir] {
+        fs::create_dir_all(dir)?;
+    }
+    log_line(config, &format!("watching {}", config.watch_dir.display()));
+    let mut state = WatchState::default();
+    loop {
+        // 扫描出错(如目录暂时无法访问)时记录日志, 下次扫描重试
+        if let Err(e) = scan(config, scheme, &mut state) {
+            log_line(
+                config,
+                &format!("cannot scan {}, {}", config.watch_dir.display(), e),
+            );
+        }
+        thread::sleep(config.interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{scheme, temp_path, write_sample_csv};
+
+    #[test]
+    fn unmovable_file_is_processed_once() {
+        let root = PathBuf::from(temp_path("watch"));
+        let _ = fs::remove_dir_all(&root);
+        let mut config = WatchConfig::new(root.join("in"), root.join("out"));
+        fs::create_dir_all(&config.watch_dir).unwrap();
+        fs::create_dir_all(&config.out_dir).unwrap();
+        // 归档目录是一个普通文件, 移动必然失败
+        config.archive_dir = root.join("archive");
+        fs::write(&config.archive_dir, "").unwrap();
+        let input = config.watch_dir.join("cases.csv");
+        write_sample_csv(&input);
+
+        let scheme = scheme();
+        let mut state = WatchState::default();
+        assert!(scan(&config, &scheme, &mut state).unwrap().is_empty());
+        assert_eq!(
+            scan(&config, &scheme, &mut state).unwrap(),
+            vec![input.clone()]
+        );
+        assert!(input.exists());
+        assert!(config.out_dir.join("cases_result.csv").exists());
+        assert!(scan(&config, &scheme, &mut state).unwrap().is_empty());
+
+        // 文件内容变化后重新处理
+        fs::write(&input, fs::read_to_string(&input).unwrap() + "\n").unwrap();
+        assert!(scan(&config, &scheme, &mut state).unwrap().is_empty());
+        assert_eq!(
+            scan(&config, &scheme, &mut state).unwrap(),
+            vec![input.clone()]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,228 @@
+use crate::case::{icd_transform, DrgCase};
+use crate::group::{group_case, DrgResult};
+use crate::scheme::DrgScheme;
+use std::string::String;
+
+// 对已分组病例的一项修改
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseEdit {
+    AddDis(String),          // 增加其他诊断
+    RemoveDis(String),       // 删除其他诊断
+    SetPrincipalOpt(String), // 更换主手术, 原主手术放入其他手术
+    SetWeight(i64),          // 修改分组使用的体重(见DrgCase::neonatal_weight)
+    SetBirthWeight(i64),     // 修改新生儿出生体重
+    SetAdmissionWeight(i64), // 修改新生儿入院体重
+}
+
+// 解析体重数值
+fn parse_weight(value: &str) -> Result<i64, String> {
+    match value.parse::<i64>() {
+        Ok(weight) if weight >= 0 => Ok(weight),
+        _ => Err(format!("invalid weight {}", value)),
+    }
+}
+
+impl CaseEdit {
+    // 从文本解析修改, 格式为"+dis 编码"、"-dis 编码"、"opt 编码"、"weight 数值"、
+    // "birth_weight 数值"、"admission_weight 数值"
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = text.split_whitespace();
+        let action = parts.next().unwrap_or("");
+        let value = parts.next().unwrap_or("").to_string();
+        if value.is_empty() || parts.next().is_some() {
+            return Err(format!("invalid edit {}", text.trim()));
+        }
+        match action {
+            "+dis" => Ok(Self::AddDis(icd_transform(value))),
+            "-dis" => Ok(Self::RemoveDis(icd_transform(value))),
+            "opt" => Ok(Self::SetPrincipalOpt(value)),
+            "weight" => Ok(Self::SetWeight(parse_weight(&value)?)),
+            "birth_weight" => Ok(Self::SetBirthWeight(parse_weight(&value)?)),
+            "admission_weight" => Ok(Self::SetAdmissionWeight(parse_weight(&value)?)),
+            _ => Err(format!("unknown edit {}", action)),
+        }
+    }
+
+    // 将修改应用到病例上
+    pub fn apply(&self, record: &mut DrgCase) -> Result<(), String> {
+        match self {
+            Self::AddDis(code) => {
+                if record.main_dis == *code || record.other_dis.contains(code) {
+                    return Err(format!("diagnosis {} already exists", code));
+                }
+                record.other_dis.push(code.clone());
+            }
+            Self::RemoveDis(code) => match record.other_dis.iter().position(|d| d == code) {
+                Some(idx) => {
+                    record.other_dis.remove(idx);
+                }
+                None => return Err(format!("secondary diagnosis {} not found", code)),
+            },
+            Self::SetPrincipalOpt(code) => {
+                if record.main_opt == *code {
+                    return Ok(());
+                }
+                let old = std::mem::replace(&mut record.main_opt, code.clone());
+                record.other_opt.retain(|o| o != code);
+                if !old.is_empty() {
+                    record.other_opt.insert(0, old);
+                }
+            }
+            // 分组优先使用出生体重, 其次入院体重, 修改实际使用的那一项
+            Self::SetWeight(weight) => {
+                let field = match (&mut record.birth_weight, &mut record.admission_weight) {
+                    (Some(w), _) | (None, Some(w)) => w,
+                    (None, None) => &mut record.weight,
+                };
+                *field = *weight;
+            }
+            Self::SetBirthWeight(weight) => record.birth_weight = Some(*weight),
+            Self::SetAdmissionWeight(weight) => record.admission_weight = Some(*weight),
+        }
+        record.recombine();
+        Ok(())
+    }
+}
+
+// 修改前后的对比
+#[derive(Debug, Clone)]
+pub struct WhatIf {
+    pub edited: DrgCase,      // 修改后的病例
+    pub before: DrgResult,    // 修改前的分组结果
+    pub after: DrgResult,     // 修改后的分组结果
+    pub removed: Vec<String>, // 只在修改前出现的分组明细说明
+    pub added: Vec<String>,   // 只在修改后出现的分组明细说明
+}
+
+// 对病例副本应用修改并重新分组, 原病例不变
+pub fn simulate(
+    record: &DrgCase,
+    edits: &[CaseEdit],
+    scheme: &DrgScheme,
+) -> Result<WhatIf, String> {
+    let mut edited = record.clone();
+    for edit in edits {
+        edit.apply(&mut edited)?;
+    }
+    let before = group_case(record, scheme);
+    let after = group_case(&edited, scheme);
+    let old_lines = before.explain();
+    let new_lines = after.explain();
+    let removed = old_lines
+        .iter()
+        .filter(|l| !new_lines.contains(l))
+        .cloned()
+        .collect();
+    let added = new_lines
+        .iter()
+        .filter(|l| !old_lines.contains(l))
+        .cloned()
+        .collect();
+    Ok(WhatIf {
+        edited,
+        before,
+        after,
+        removed,
+        added,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{sample_case, scheme};
+
+    fn neonate() -> DrgCase {
+        let mut case = sample_case("P07.300", &[], &[], 0.0);
+        case.age_days = Some(3);
+        case.birth_weight = Some(1200);
+        case
+    }
+
+    #[test]
+    fn weight_edit_changes_the_weight_used_for_grouping() {
+        let mut record = neonate();
+        CaseEdit::parse("weight 2600")
+            .unwrap()
+            .apply(&mut record)
+            .unwrap();
+        assert_eq!(record.neonatal_weight(), 2600);
+        assert_eq!(record.weight, 3000);
+    }
+
+    // J20.900伴I10.x00, 主手术32.2904, 分组为EB25
+    fn bronchitis_with_operation() -> DrgCase {
+        sample_case("J20.900", &["I10.x00"], &["32.2904"], 40.0)
+    }
+
+    #[test]
+    fn adding_a_cc_reports_the_new_explanation() {
+        let scheme = scheme();
+        let edits = [CaseEdit::parse("+dis E87.102").unwrap()];
+        let whatif = simulate(&bronchitis_with_operation(), &edits, &scheme).unwrap();
+        assert_eq!(whatif.before.drg, "EB25");
+        assert_eq!(whatif.after.drg, "EB23");
+        assert_eq!(whatif.removed, vec!["CC/MCC: none".to_string()]);
+        assert_eq!(whatif.added.len(), 1);
+        assert!(whatif.added[0].starts_with("E87.102 CC counted"));
+    }
+
+    #[test]
+    fn removing_a_cc_reports_the_lost_explanation() {
+        let scheme = scheme();
+        let record = sample_case("J20.900", &["E87.102", "I10.x00"], &["32.2904"], 40.0);
+        let edits = [CaseEdit::parse("-dis E87.102").unwrap()];
+        let whatif = simulate(&record, &edits, &scheme).unwrap();
+        assert_eq!(whatif.edited.other_dis, vec!["I10.x00".to_string()]);
+        assert_eq!(whatif.after.drg, "EB25");
+        assert!(whatif.removed[0].starts_with("E87.102 CC counted"));
+        assert_eq!(whatif.added, vec!["CC/MCC: none".to_string()]);
+        // 原病例不变
+        assert_eq!(record.other_dis.len(), 2);
+    }
+
+    #[test]
+    fn diagnosis_edits_reject_duplicates_and_missing_codes() {
+        let mut record = bronchitis_with_operation();
+        assert!(CaseEdit::parse("+dis I10.x00")
+            .unwrap()
+            .apply(&mut record)
+            .is_err());
+        assert!(CaseEdit::parse("-dis E87.102")
+            .unwrap()
+            .apply(&mut record)
+            .is_err());
+        assert!(CaseEdit::parse("+dis").is_err());
+        assert!(CaseEdit::parse("dis E87.102").is_err());
+    }
+
+    #[test]
+    fn principal_operation_edit_moves_the_old_one_to_other_operations() {
+        let scheme = scheme();
+        let record = sample_case("J20.900", &["E87.102", "I10.x00"], &[], 40.0);
+        let edits = [CaseEdit::parse("opt 32.2904").unwrap()];
+        let whatif = simulate(&record, &edits, &scheme).unwrap();
+        assert_eq!(whatif.before.drg, "EX23");
+        assert_eq!(whatif.after.drg, "EB23");
+        // 并发症说明不变
+        assert!(whatif.removed.is_empty() && whatif.added.is_empty());
+
+        let mut edited = whatif.edited;
+        CaseEdit::parse("opt 39.9014")
+            .unwrap()
+            .apply(&mut edited)
+            .unwrap();
+        assert_eq!(edited.main_opt, "39.9014");
+        assert_eq!(edited.other_opt, vec!["32.2904".to_string()]);
+    }
+
+    #[test]
+    fn birth_weight_edit_regroups_neonate() {
+        let scheme = scheme();
+        let edits = [CaseEdit::parse("birth_weight 3200").unwrap()];
+        let whatif = simulate(&neonate(), &edits, &scheme).unwrap();
+        assert_eq!(whatif.edited.birth_weight, Some(3200));
+        assert_eq!(whatif.before.adrg, "PS1");
+        assert_eq!(whatif.after.adrg, "PS4");
+    }
+}